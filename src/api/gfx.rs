@@ -1,73 +1,179 @@
 use std::sync::MutexGuard;
 
-use crate::{draw_state, font::FONT, get_map, set_map};
+use crate::font::{self, FONT};
+use crate::palette::{self, rgb_palette_index};
+use crate::{draw_state, get_map, set_map, CART, RGB_PALETTE};
 use crate::{
-    get_pixel, get_sprite, get_sprite_flag, set_pixel, ColorPalette, TerminalLocation, HEIGHT, MEM,
-    WIDTH,
+    fill_pixel, get_pixel, get_sprite, get_sprite_flag, get_spritesheet_pixel, set_pixel,
+    ColorPalette, TerminalLocation, HEIGHT, MEM, WIDTH,
 };
 
 pub fn camera(x: i32, y: i32) {
     draw_state::set_camera_offset(None, Some(x), Some(y));
 }
 
-pub fn circ(x: i32, y: i32, r: i32, col: i32) {
-    let col = ColorPalette::from(col);
-    let mut x_off = 0;
-    let mut y_off = r;
+/// Walks one octant of the midpoint circle of radius `r`, calling `step` with the x and y
+/// offsets of every point from the center
+fn circle_octant(r: i32, mut step: impl FnMut(i32, i32)) {
+    let mut x_off = r;
+    let mut y_off = 0;
     let mut d = 1 - r;
-    let mut mem = MEM.lock().unwrap();
-    loop {
-        for y_idx in &[-y_off, y_off] {
-            for x_idx in &[-x_off, x_off] {
-                let loc0 = TerminalLocation(x + x_idx, y + y_idx).apply_camera_offset(Some(&mem));
-                let loc1 = TerminalLocation(x + y_idx, y + x_idx).apply_camera_offset(Some(&mem));
-                set_pixel(Some(&mut mem), loc0, col);
-                set_pixel(Some(&mut mem), loc1, col);
-            }
-        }
+    while y_off <= x_off {
+        step(x_off, y_off);
 
+        y_off += 1;
         if d < 0 {
-            d += (2 * x_off) + 1;
+            d += (2 * y_off) + 1;
         } else {
-            y_off -= 1;
-            d += (2 * x_off) - (2 * y_off) + 1;
+            x_off -= 1;
+            d += (2 * (y_off - x_off)) + 1;
+        }
+    }
+}
+
+/// Calls `point` with the offset of every point on the outline of a circle of radius `r`
+fn circle_points(r: i32, mut point: impl FnMut(i32, i32)) {
+    circle_octant(r, |x_off, y_off| {
+        for (px, py) in &[
+            (x_off, y_off),
+            (y_off, x_off),
+            (-x_off, y_off),
+            (-y_off, x_off),
+            (-x_off, -y_off),
+            (-y_off, -x_off),
+            (x_off, -y_off),
+            (y_off, -x_off),
+        ] {
+            point(*px, *py);
         }
-        x_off += 1;
+    });
+}
 
-        if x_off >= y_off {
-            break;
+/// Calls `span` with the left x, right x and y offsets of every row of a filled circle of
+/// radius `r`
+fn circle_spans(r: i32, mut span: impl FnMut(i32, i32, i32)) {
+    circle_octant(r, |x_off, y_off| {
+        for (half_width, py) in &[
+            (x_off, y_off),
+            (y_off, x_off),
+            (x_off, -y_off),
+            (y_off, -x_off),
+        ] {
+            span(-half_width, *half_width, *py);
         }
+    });
+}
+
+pub fn circ(x: i32, y: i32, r: i32, col: i32) {
+    if r < 0 {
+        return;
     }
+
+    let mut mem = MEM.lock().unwrap();
+    circle_points(r, |px, py| {
+        let loc = TerminalLocation(x + px, y + py).apply_camera_offset(Some(&mem));
+        fill_pixel(Some(&mut mem), loc, col);
+    });
 }
 
 pub fn circfill(x: i32, y: i32, r: i32, col: i32) {
-    let col = ColorPalette::from(col);
-    let mut x_off = 0;
-    let mut y_off = r;
-    let mut d = 1 - r;
+    if r < 0 {
+        return;
+    }
+
     let mut mem = MEM.lock().unwrap();
-    loop {
-        for y_idx in -y_off..y_off {
-            for x_idx in -x_off..x_off {
-                let loc0 = TerminalLocation(x + x_idx, y + y_idx).apply_camera_offset(Some(&mem));
-                let loc1 = TerminalLocation(x + y_idx, y + x_idx).apply_camera_offset(Some(&mem));
-                set_pixel(Some(&mut mem), loc0, col);
-                set_pixel(Some(&mut mem), loc1, col);
-            }
+    circle_spans(r, |left, right, py| {
+        for px in left..=right {
+            let loc = TerminalLocation(x + px, y + py).apply_camera_offset(Some(&mem));
+            fill_pixel(Some(&mut mem), loc, col);
         }
+    });
+}
 
-        if d < 0 {
-            d += (2 * x_off) + 1;
-        } else {
-            y_off -= 1;
-            d += (2 * x_off) - (2 * y_off) + 1;
+/// Walks the outline of the ellipse inscribed in the rectangle (`x0`, `y0`), (`x1`, `y1`),
+/// calling `span` with the left x, right x and y of every outline row step
+fn oval_spans(x0: i32, y0: i32, x1: i32, y1: i32, mut span: impl FnMut(i32, i32, i32)) {
+    let a = (x1 - x0).abs() as i64;
+    let b = (y1 - y0).abs() as i64;
+    let b1 = b & 1;
+    let mut dx = 4 * (1 - a) * b * b;
+    let mut dy = 4 * (b1 + 1) * a * a;
+    let mut err = dx + dy + (b1 * a * a);
+
+    let mut left = x0.min(x1) as i64;
+    let mut right = x0.max(x1) as i64;
+    let mut bottom = y0.min(y1) as i64 + ((b + 1) / 2);
+    let mut top = bottom - b1;
+    let a8 = 8 * a * a;
+    let b8 = 8 * b * b;
+
+    loop {
+        span(left as i32, right as i32, bottom as i32);
+        span(left as i32, right as i32, top as i32);
+
+        let err2 = 2 * err;
+        if err2 <= dy {
+            bottom += 1;
+            top -= 1;
+            dy += a8;
+            err += dy;
+        }
+        if err2 >= dx || 2 * err > dy {
+            left += 1;
+            right -= 1;
+            dx += b8;
+            err += dx;
         }
-        x_off += 1;
 
-        if x_off >= y_off {
+        if left > right {
             break;
         }
     }
+
+    // Flat ellipses stop early, finish off their tips
+    while bottom - top <= b {
+        span((left - 1) as i32, (right + 1) as i32, bottom as i32);
+        span((left - 1) as i32, (right + 1) as i32, top as i32);
+        bottom += 1;
+        top -= 1;
+    }
+}
+
+pub fn oval(x0: i32, y0: i32, x1: i32, y1: i32, col: i32) {
+    let mut mem = MEM.lock().unwrap();
+    oval_spans(x0, y0, x1, y1, |left, right, y| {
+        for x in &[left, right] {
+            let loc = TerminalLocation(*x, y).apply_camera_offset(Some(&mem));
+            fill_pixel(Some(&mut mem), loc, col);
+        }
+    });
+}
+
+pub fn ovalfill(x0: i32, y0: i32, x1: i32, y1: i32, col: i32) {
+    let mut mem = MEM.lock().unwrap();
+    oval_spans(x0, y0, x1, y1, |left, right, y| {
+        for x in left..=right {
+            let loc = TerminalLocation(x, y).apply_camera_offset(Some(&mem));
+            fill_pixel(Some(&mut mem), loc, col);
+        }
+    });
+}
+
+/// Sets the clip rectangle in screen space and returns the previous one as (x, y, w, h)
+pub fn clip(x: i32, y: i32, w: i32, h: i32, clip_previous: i32) -> (i32, i32, i32, i32) {
+    let mut mem = MEM.lock().unwrap();
+    let (prev_x0, prev_y0, prev_x1, prev_y1) = draw_state::get_clip(Some(&mem));
+
+    let (mut x0, mut y0, mut x1, mut y1) = (x, y, x + w, y + h);
+    if clip_previous != 0 {
+        x0 = x0.max(prev_x0);
+        y0 = y0.max(prev_y0);
+        x1 = x1.min(prev_x1);
+        y1 = y1.min(prev_y1);
+    }
+
+    draw_state::set_clip(Some(&mut mem), x0, y0, x1.max(x0), y1.max(y0));
+    (prev_x0, prev_y0, prev_x1 - prev_x0, prev_y1 - prev_y0)
 }
 
 pub fn clrpal() {
@@ -77,12 +183,26 @@ pub fn clrpal() {
 }
 
 pub fn pal(c0: i32, c1: i32, p: i32) {
-    let col0 = ColorPalette::from(c0);
-    let col1 = ColorPalette::from(c1);
+    let col0 = ColorPalette::from(c0 & 0b1111);
     if p == 0 {
+        let col1 = ColorPalette::from(c1 & 0b1111);
         draw_state::set_draw_palette(None, col0, Some(col1), None);
     } else if p == 1 {
-        draw_state::set_screen_palette(None, col0, col1);
+        draw_state::set_screen_palette(None, col0, c1 as u8);
+    }
+}
+
+/// WARS-8 extension, sets the displayed RGB value (0xRRGGBB) of screen color `col`
+pub fn palrgb(col: i32, rgb: i32) {
+    RGB_PALETTE.lock().unwrap()[rgb_palette_index(col as u8)] =
+        ((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8);
+}
+
+/// WARS-8 extension, resets the displayed RGB values to the palette the cart declares
+pub fn palrgb_reset() {
+    match CART.lock().unwrap().as_deref() {
+        Some(cart) => palette::load_rgb_palette(cart.palette()),
+        None => palette::load_rgb_palette(&[]),
     }
 }
 
@@ -92,10 +212,52 @@ pub fn palt(col: i32, transparent: i32) {
 }
 
 pub fn pset(x: i32, y: i32, color: i32) {
-    let idx = TerminalLocation(x, y);
     let mut mem = MEM.lock().unwrap();
-    let col = ColorPalette::from(color).apply_palette_mod(Some(&mem), false);
-    set_pixel(Some(&mut mem), idx, col);
+    let idx = TerminalLocation(x, y).apply_camera_offset(Some(&mem));
+    fill_pixel(Some(&mut mem), idx, color);
+}
+
+pub fn line(x0: i32, y0: i32, x1: i32, y1: i32, color: i32) {
+    let mut mem = MEM.lock().unwrap();
+
+    let dx = (x1 - x0).abs();
+    let dy = -(y1 - y0).abs();
+    let step_x = if x0 < x1 { 1 } else { -1 };
+    let step_y = if y0 < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let (mut x, mut y) = (x0, y0);
+    loop {
+        let loc = TerminalLocation(x, y).apply_camera_offset(Some(&mem));
+        fill_pixel(Some(&mut mem), loc, color);
+
+        if x == x1 && y == y1 {
+            break;
+        }
+
+        let err2 = 2 * err;
+        if err2 >= dy {
+            err += dy;
+            x += step_x;
+        }
+        if err2 <= dx {
+            err += dx;
+            y += step_y;
+        }
+    }
+
+    draw_state::set_line_endpoint(Some(&mut mem), Some((x1, y1)));
+}
+
+/// Continues a line from the last endpoint, only storing the endpoint if there is none
+pub fn line_to(x1: i32, y1: i32, color: i32) {
+    match draw_state::get_line_endpoint(None) {
+        Some((x0, y0)) => line(x0, y0, x1, y1, color),
+        None => draw_state::set_line_endpoint(None, Some((x1, y1))),
+    }
+}
+
+pub fn line_reset() {
+    draw_state::set_line_endpoint(None, None);
 }
 
 pub fn pget(x: i32, y: i32) -> i32 {
@@ -105,67 +267,336 @@ pub fn pget(x: i32, y: i32) -> i32 {
 
 pub fn rect(x0: i32, y0: i32, x1: i32, y1: i32, color: i32) {
     let mut mem = MEM.lock().unwrap();
-    let color = ColorPalette::from(color).apply_palette_mod(Some(&mem), false);
     for x in x0..=x1 {
         let y0_loc = TerminalLocation(x, y0).apply_camera_offset(Some(&mem));
         let y1_loc = TerminalLocation(x, y1).apply_camera_offset(Some(&mem));
 
-        set_pixel(Some(&mut mem), y0_loc, color);
-        set_pixel(Some(&mut mem), y1_loc, color);
+        fill_pixel(Some(&mut mem), y0_loc, color);
+        fill_pixel(Some(&mut mem), y1_loc, color);
     }
 
     for y in y0..=y1 {
         let x0_loc = TerminalLocation(x0, y).apply_camera_offset(Some(&mem));
         let x1_loc = TerminalLocation(x1, y).apply_camera_offset(Some(&mem));
-        set_pixel(Some(&mut mem), x0_loc, color);
-        set_pixel(Some(&mut mem), x1_loc, color);
+        fill_pixel(Some(&mut mem), x0_loc, color);
+        fill_pixel(Some(&mut mem), x1_loc, color);
     }
 }
 
 pub fn rectfill(x0: i32, y0: i32, x1: i32, y1: i32, color: i32) {
     let mut mem = MEM.lock().unwrap();
-    let color = ColorPalette::from(color).apply_palette_mod(Some(&mem), false);
     for x in x0..=x1 {
         for y in y0..=y1 {
             let loc = TerminalLocation(x, y).apply_camera_offset(Some(&mem));
-            set_pixel(Some(&mut mem), loc, color);
+            fill_pixel(Some(&mut mem), loc, color);
         }
     }
 }
 
 pub fn cls(color: i32) {
-    rectfill(0, 0, WIDTH - 1, HEIGHT - 1, color);
+    clear_screen(&mut MEM.lock().unwrap(), color);
 }
 
-pub fn print(string: String, x: i32, y: i32, col: i32) {
-    let string = string.to_ascii_uppercase();
-    let mut mem = MEM.lock().unwrap();
-    let color = ColorPalette::from(col).apply_palette_mod(Some(&mem), false);
-    let mut offset = 0;
-    for ch in string.chars().collect::<Vec<char>>() {
-        if let Some(font) = FONT.get(&ch) {
-            for row_offset in 0..6 {
-                let row = font[row_offset];
-                for col_idx in 0..4 {
-                    if row[col_idx] {
-                        let loc = TerminalLocation(
-                            x + (offset * 4) + col_idx as i32,
-                            y + row_offset as i32,
-                        )
-                        .apply_camera_offset(Some(&mem));
-                        set_pixel(Some(&mut mem), loc, color);
+/// Clears the screen to `color` and moves the print cursor back to (0, 0)
+fn clear_screen(mem: &mut MutexGuard<[u8; 0x8000]>, color: i32) {
+    let color = ColorPalette::from(color & 0b1111).apply_palette_mod(Some(mem), false);
+    for x in 0..WIDTH {
+        for y in 0..HEIGHT {
+            set_pixel(Some(&mut *mem), TerminalLocation(x, y), color);
+        }
+    }
+    draw_state::set_print_cursor(Some(mem), Some(0), Some(0));
+}
+
+pub fn fillp(pattern: i32, transparent: i32) {
+    draw_state::set_fill_pattern(None, pattern as u16, transparent != 0);
+}
+
+/// Text style set by the P8SCII control codes while printing
+struct TextStyle {
+    custom_font: bool,
+    fg: ColorPalette,
+    bg: Option<ColorPalette>,
+    wide: bool,
+    tall: bool,
+    invert: bool,
+}
+
+impl TextStyle {
+    /// Distance between lines of text
+    fn line_height(&self, mem: &MutexGuard<[u8; 0x8000]>) -> i32 {
+        let height = match self.custom_font {
+            true => mem[0x5602] as i32,
+            false => 6,
+        };
+        match self.tall {
+            true => height * 2,
+            false => height,
+        }
+    }
+}
+
+fn text_color(mem: &MutexGuard<[u8; 0x8000]>, col: i32) -> ColorPalette {
+    ColorPalette::from(col & 0b1111).apply_palette_mod(Some(mem), false)
+}
+
+/// Number of parameter bytes taken by `\^` commands that aren't supported, so they can be skipped
+/// instead of printed. `\^:` and `\^.` take a one-off glyph as 16 hex digits or 8 raw bytes
+fn special_command_params(command: u8) -> usize {
+    match command {
+        b'd' | b'r' | b's' | b'x' | b'y' => 1,
+        b'j' => 2,
+        b':' => 16,
+        b'.' => 8,
+        _ => 0,
+    }
+}
+
+/// Reads a control code parameter, `0`-`9` then `a`-`z` for 10-35
+fn control_param(ch: Option<&u8>) -> i32 {
+    match ch {
+        Some(ch @ b'0'..=b'9') => (ch - b'0') as i32,
+        Some(ch @ b'a'..=b'z') => (ch - b'a') as i32 + 10,
+        _ => 0,
+    }
+}
+
+/// Returns the width, height and draw offset of the cell for `ch`.
+///
+/// Built-in glyphs are 5 rows with a row and column of spacing. For the custom font at 0x5600,
+/// 0x5600/0x5601 hold the width of glyphs below/from 128, 0x5602 the height and 0x5603/0x5604
+/// the draw offset. If bit 0 of 0x5605 is set 0x5608..0x567f hold a nibble per glyph from 16
+/// with a signed width adjustment in the low 3 bits and a 1 pixel y offset in bit 3
+fn glyph_cell(mem: &MutexGuard<[u8; 0x8000]>, ch: u8, custom_font: bool) -> (i32, i32, i32, i32) {
+    if !custom_font {
+        return (font::glyph_width(ch), 6, 0, 0);
+    }
+
+    let mut width = match ch < 128 {
+        true => mem[0x5600] as i32,
+        false => mem[0x5601] as i32,
+    };
+    let mut y_offset = mem[0x5604] as i8 as i32;
+
+    if mem[0x5605] & 0b1 != 0 {
+        let adjust = mem[0x5608 + ((ch - 16) / 2) as usize] >> (((ch - 16) % 2) * 4);
+        width += (((adjust & 0b111) << 5) as i8 >> 5) as i32;
+        y_offset += ((adjust >> 3) & 0b1) as i32;
+    }
+
+    let x_offset = mem[0x5603] as i8 as i32;
+    (width, mem[0x5602] as i32, x_offset, y_offset)
+}
+
+/// Checks if pixel (`x`, `y`) of glyph `ch` is set. Custom font glyphs are 8 bytes (rows) at
+/// 0x5600 + ch * 8 with the leftmost pixel in bit 0
+fn glyph_pixel(mem: &MutexGuard<[u8; 0x8000]>, ch: u8, custom_font: bool, x: i32, y: i32) -> bool {
+    if custom_font {
+        x < 8 && y < 8 && mem[0x5600 + (ch as usize * 8) + y as usize] & (1 << x) != 0
+    } else {
+        x < 8 && y < 5 && FONT[ch as usize][y as usize] & (0b1000_0000 >> x) != 0
+    }
+}
+
+/// Draws glyph `ch` at (`x`, `y`) in `style`
+fn draw_glyph(mem: &mut MutexGuard<[u8; 0x8000]>, ch: u8, x: i32, y: i32, style: &TextStyle) {
+    let (width, height, x_offset, y_offset) = glyph_cell(mem, ch, style.custom_font);
+    let scale_x = if style.wide { 2 } else { 1 };
+    let scale_y = if style.tall { 2 } else { 1 };
+
+    for row_idx in 0..height {
+        for col_idx in 0..width {
+            let color = match glyph_pixel(mem, ch, style.custom_font, col_idx, row_idx) {
+                set if set != style.invert => style.fg,
+                _ => match style.bg {
+                    Some(bg) => bg,
+                    None => continue,
+                },
+            };
+
+            for sub_y in 0..scale_y {
+                for sub_x in 0..scale_x {
+                    let loc = TerminalLocation(
+                        x + x_offset + (col_idx * scale_x) + sub_x,
+                        y + y_offset + (row_idx * scale_y) + sub_y,
+                    )
+                    .apply_camera_offset(Some(&*mem));
+                    set_pixel(Some(&mut *mem), loc, color);
+                }
+            }
+        }
+    }
+}
+
+/// Draws P8SCII `string` at (`x`, `y`) handling its control codes, returns the x position after
+/// the end of the text and the y position of the next line. With `draw` false the text is only
+/// laid out, to measure it
+fn draw_text(
+    mem: &mut MutexGuard<[u8; 0x8000]>,
+    string: &[u8],
+    x: i32,
+    y: i32,
+    col: i32,
+    draw: bool,
+) -> (i32, i32) {
+    let mut style = TextStyle {
+        custom_font: false,
+        fg: text_color(mem, col),
+        bg: None,
+        wide: false,
+        tall: false,
+        invert: false,
+    };
+    let mut home_x = x;
+    let mut cursor_x = x;
+    let mut cursor_y = y;
+    let mut repeat = 1;
+
+    let mut bytes = string.iter();
+    while let Some(&ch) = bytes.next() {
+        match ch {
+            // \0 ends the string
+            0 => break,
+            // \* repeats the next character
+            1 => {
+                repeat = control_param(bytes.next());
+                continue;
+            }
+            // \# sets the background color
+            2 => style.bg = Some(text_color(mem, control_param(bytes.next()))),
+            // \- and \| move the cursor by -16 to 19 pixels
+            3 => cursor_x += control_param(bytes.next()) - 16,
+            4 => cursor_y += control_param(bytes.next()) - 16,
+            // \^ special commands, \^- turns a style off
+            6 => match bytes.next() {
+                Some(b'w') => style.wide = true,
+                Some(b't') => style.tall = true,
+                Some(b'i') => style.invert = true,
+                Some(b'-') => match bytes.next() {
+                    Some(b'w') => style.wide = false,
+                    Some(b't') => style.tall = false,
+                    Some(b'i') => style.invert = false,
+                    _ => {}
+                },
+                Some(b'c') => {
+                    let color = text_color(mem, control_param(bytes.next()));
+                    for x in (0..WIDTH).filter(|_| draw) {
+                        for y in 0..HEIGHT {
+                            set_pixel(Some(&mut *mem), TerminalLocation(x, y), color);
+                        }
+                    }
+                    home_x = 0;
+                    cursor_x = 0;
+                    cursor_y = 0;
+                }
+                // \^@ (poke the following bytes) and \^! (poke the rest of the string) aren't
+                // supported, skip what they would poke
+                Some(b'@') => {
+                    let header: Vec<u8> = bytes.by_ref().take(8).copied().collect();
+                    let count = std::str::from_utf8(header.get(4..).unwrap_or(&[]))
+                        .ok()
+                        .and_then(|count| usize::from_str_radix(count, 16).ok())
+                        .unwrap_or(0);
+                    bytes.by_ref().take(count).for_each(drop);
+                }
+                Some(b'!') => break,
+                Some(&command) => {
+                    let params = special_command_params(command);
+                    bytes.by_ref().take(params).for_each(drop);
+                }
+                None => {}
+            },
+            9 => cursor_x = home_x + (((cursor_x - home_x) / 16) + 1) * 16,
+            10 => {
+                cursor_x = home_x;
+                cursor_y += style.line_height(mem);
+            }
+            // \f sets the foreground color
+            12 => style.fg = text_color(mem, control_param(bytes.next())),
+            13 => cursor_x = home_x,
+            14 => style.custom_font = true,
+            15 => style.custom_font = false,
+            ch @ 16..=255 => {
+                for _ in 0..repeat {
+                    // Wrap to the next line when the glyph would go past the right edge
+                    let scale_x = if style.wide { 2 } else { 1 };
+                    let width = glyph_cell(mem, ch, style.custom_font).0 * scale_x;
+                    let right = TerminalLocation(cursor_x + width, cursor_y)
+                        .apply_camera_offset(Some(&*mem))
+                        .0;
+                    if cursor_x != home_x && right > WIDTH {
+                        cursor_x = home_x;
+                        cursor_y += style.line_height(mem);
                     }
+
+                    if draw {
+                        draw_glyph(mem, ch, cursor_x, cursor_y, &style);
+                    }
+                    cursor_x += width;
                 }
             }
+            // Other control codes are ignored
+            _ => {}
         }
-        offset += 1;
+        repeat = 1;
+    }
+
+    (cursor_x, cursor_y + style.line_height(mem))
+}
+
+/// Prints P8SCII `string` at (`x`, `y`) and moves the print cursor to the start of the next
+/// line, returns the x position after the end of the text
+pub fn print(string: &[u8], x: i32, y: i32, col: i32) -> i32 {
+    let mut mem = MEM.lock().unwrap();
+    let (end_x, next_y) = draw_text(&mut mem, string, x, y, col, true);
+    draw_state::set_print_cursor(Some(&mut mem), Some(x), Some(next_y));
+    end_x
+}
+
+/// Prints P8SCII `string` at the print cursor like `print`, scrolling the screen up first if the
+/// text would go past the bottom
+pub fn print_cursor(string: &[u8], col: i32) -> i32 {
+    let mut mem = MEM.lock().unwrap();
+    let (x, mut y) = draw_state::get_print_cursor(Some(&mem));
+
+    // Lay the text out first to find where it ends, after wrapping and tall or custom fonts
+    let (_, next_y) = draw_text(&mut mem, string, x, y, col, false);
+    let overflow = (next_y - HEIGHT).min(y);
+    if overflow > 0 {
+        // Screen memory is 64 bytes per row from 0x6000
+        let offset = overflow as usize * 64;
+        mem.copy_within((0x6000 + offset)..0x8000, 0x6000);
+        mem[(0x8000 - offset)..0x8000].fill(0);
+        y -= overflow;
     }
+
+    drop(mem);
+    print(string, x, y, col)
+}
+
+/// Sets the print cursor to (`x`, `y`) and the pen color to `col` if it is given
+pub fn cursor(x: i32, y: i32, col: Option<i32>) {
+    let mut mem = MEM.lock().unwrap();
+    draw_state::set_print_cursor(Some(&mut mem), Some(x), Some(y));
+    if let Some(col) = col {
+        draw_state::set_pen_color(Some(&mut mem), col);
+    }
+}
+
+/// Sets the pen color used when a color is not given and returns the previous one. The high
+/// nibble is kept as the secondary color of fill patterns
+pub fn color(col: i32) -> i32 {
+    let mut mem = MEM.lock().unwrap();
+    let previous = draw_state::get_pen_color(Some(&mem));
+    draw_state::set_pen_color(Some(&mut mem), col);
+    previous
 }
 
 pub fn printh(str: String) {
     println!("{}", str);
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn spr(
     mem: Option<&mut MutexGuard<[u8; 0x8000]>>,
     idx: i32,
@@ -193,10 +624,10 @@ pub fn spr(
 
     for height_idx in 0..=(height_px / 8) {
         for width_idx in 0..=(width_px / 8) {
-            let sprite = get_sprite(Some(&mem), (height_idx * 0x10) + idx + width_idx);
+            let sprite = get_sprite(Some(mem), (height_idx * 0x10) + idx + width_idx);
             {
-                let row_px: i32 = (width_px - (8 * width_idx)).min(8).max(-8);
-                let height_px: i32 = (height_px - (8 * height_idx)).min(8).max(-8);
+                let row_px: i32 = (width_px - (8 * width_idx)).clamp(-8, 8);
+                let height_px: i32 = (height_px - (8 * height_idx)).clamp(-8, 8);
 
                 for row_idx in 0..height_px {
                     for col_idx in 0..row_px {
@@ -227,6 +658,7 @@ pub fn spr(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn sspr(
     sx: i32,
     sy: i32,
@@ -239,37 +671,33 @@ pub fn sspr(
     flip_x: i32,
     flip_y: i32,
 ) {
+    if sw <= 0 || sh <= 0 || dw <= 0 || dh <= 0 {
+        return;
+    }
+
     let mut mem = MEM.lock().unwrap();
 
     let flip_x = flip_x != 0;
     let flip_y = flip_y != 0;
 
-    for row_offset in 0..sh {
-        for col_offset in 0..sw {
-            for y_str_offset in 0..dh {
-                for x_str_offset in 0..dw {
-                    let mut loc = TerminalLocation(
-                        dx + (col_offset * dw) + x_str_offset,
-                        dy + (row_offset * dh) + y_str_offset,
-                    );
-
-                    if flip_x {
-                        loc.0 = dx + (col_offset * dw) + (dw - 1 - x_str_offset);
-                    }
-
-                    if flip_y {
-                        loc.1 = dy + (row_offset * dh) + (dh - 1 - y_str_offset);
-                    }
+    for y_offset in 0..dh {
+        let src_y = match flip_y {
+            false => sy + (y_offset * sh) / dh,
+            true => sy + ((dh - 1 - y_offset) * sh) / dh,
+        };
 
-                    loc = loc.apply_camera_offset(Some(&mem));
+        for x_offset in 0..dw {
+            let src_x = match flip_x {
+                false => sx + (x_offset * sw) / dw,
+                true => sx + ((dw - 1 - x_offset) * sw) / dw,
+            };
 
-                    let offset = ((sx + col_offset) / 2) + ((sy + row_offset) * 64);
-                    let col = ColorPalette::from(mem[offset as usize] as i32)
-                        .apply_palette_mod(Some(&mem), false);
-                    if col != ColorPalette::Black {
-                        set_pixel(Some(&mut mem), loc, col);
-                    }
-                }
+            let col = get_spritesheet_pixel(Some(&mem), src_x, src_y)
+                .apply_palette_mod(Some(&mem), false);
+            if col != ColorPalette::Black {
+                let loc =
+                    TerminalLocation(dx + x_offset, dy + y_offset).apply_camera_offset(Some(&mem));
+                set_pixel(Some(&mut mem), loc, col);
             }
         }
     }
@@ -280,8 +708,8 @@ pub fn map(cel_x: i32, cel_y: i32, scr_x: i32, scr_y: i32, cel_w: i32, cel_h: i3
     let layer = layer as u8;
     for y in 0..cel_h {
         for x in 0..cel_w {
-            let loc = TerminalLocation((scr_y + (y * 8)) as i32, (scr_x + (x * 8)) as i32)
-                .apply_camera_offset(Some(&mem));
+            let loc =
+                TerminalLocation(scr_y + (y * 8), scr_x + (x * 8)).apply_camera_offset(Some(&mem));
             if loc.is_valid() {
                 let val = get_map(Some(&mut mem), cel_x + x, cel_y + y) as i32;
                 if get_sprite_flag(Some(&mem), val, None) & layer == layer {
@@ -301,6 +729,88 @@ pub fn map(cel_x: i32, cel_y: i32, scr_x: i32, scr_y: i32, cel_w: i32, cel_h: i3
     }
 }
 
+/// Draws a line textured from the map, `mx`/`my` and `mdx`/`mdy` are in tiles
+#[allow(clippy::too_many_arguments)]
+pub fn tline(
+    x0: i32,
+    y0: i32,
+    x1: i32,
+    y1: i32,
+    mx: f32,
+    my: f32,
+    mdx: f32,
+    mdy: f32,
+    layers: i32,
+) {
+    let mut mem = MEM.lock().unwrap();
+    let (mask_w, mask_h, off_x, off_y) = draw_state::get_tline_mask(Some(&mem));
+    let layers = layers as u8;
+
+    // 16.16 fixed point, one tile is 0x10000 and one pixel 0x2000
+    let mut mx = (mx * 65536.0) as i32;
+    let mut my = (my * 65536.0) as i32;
+    let mdx = (mdx * 65536.0) as i32;
+    let mdy = (mdy * 65536.0) as i32;
+
+    let mut cached: Option<(i32, [[ColorPalette; 8]; 8])> = None;
+
+    let dx = (x1 - x0).abs();
+    let dy = -(y1 - y0).abs();
+    let step_x = if x0 < x1 { 1 } else { -1 };
+    let step_y = if y0 < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let (mut x, mut y) = (x0, y0);
+    loop {
+        let mut tile_x = mx >> 16;
+        let mut tile_y = my >> 16;
+        if mask_w != 0 {
+            tile_x = (tile_x & (mask_w - 1)) + off_x;
+        }
+        if mask_h != 0 {
+            tile_y = (tile_y & (mask_h - 1)) + off_y;
+        }
+
+        if (0..128).contains(&tile_x) && (0..64).contains(&tile_y) {
+            let val = get_map(Some(&mut mem), tile_x, tile_y) as i32;
+            if val != 0 && get_sprite_flag(Some(&mem), val, None) & layers == layers {
+                let sprite = match cached {
+                    Some((idx, sprite)) if idx == val => sprite,
+                    _ => {
+                        let sprite = get_sprite(Some(&mem), val);
+                        cached = Some((val, sprite));
+                        sprite
+                    }
+                };
+
+                let px = ((mx >> 13) & 0b111) as usize;
+                let py = ((my >> 13) & 0b111) as usize;
+                let col = sprite[py][px].apply_palette_mod(Some(&mem), false);
+                if col != ColorPalette::Black {
+                    let loc = TerminalLocation(x, y).apply_camera_offset(Some(&mem));
+                    set_pixel(Some(&mut mem), loc, col);
+                }
+            }
+        }
+
+        mx = mx.wrapping_add(mdx);
+        my = my.wrapping_add(mdy);
+
+        if x == x1 && y == y1 {
+            break;
+        }
+
+        let err2 = 2 * err;
+        if err2 >= dy {
+            err += dy;
+            x += step_x;
+        }
+        if err2 <= dx {
+            err += dx;
+            y += step_y;
+        }
+    }
+}
+
 pub fn mget(x: i32, y: i32) -> u8 {
     get_map(None, x, y)
 }
@@ -308,3 +818,245 @@ pub fn mget(x: i32, y: i32) -> u8 {
 pub fn mset(x: i32, y: i32, val: u8) {
     set_map(None, x, y, val);
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::fmt::Write;
+    use std::sync::Mutex;
+
+    use super::{circle_points, circle_spans, clear_screen, draw_text, oval_spans};
+    use crate::draw_state;
+
+    /// The reference dumps in tests/fixtures/gfx/pico8 come from running capture.p8 there in
+    /// PICO-8, they are never blessed from this implementation. The comparisons are ignored until
+    /// the dumps are checked in
+    fn assert_pico8(name: &str, actual: &str) {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join("gfx")
+            .join("pico8")
+            .join(name);
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|why| panic!("Unable to read PICO-8 dump {}. Reason: {}", name, why));
+        let mismatch = expected
+            .lines()
+            .zip(actual.lines())
+            .position(|(e, a)| e != a);
+        assert!(
+            expected == actual,
+            "Output differs from PICO-8 dump {} at line {}",
+            name,
+            mismatch.unwrap_or(expected.lines().count().min(actual.lines().count())) + 1
+        );
+    }
+
+    /// Odd rectangles for the oval tests: degenerate, flat, thin, swapped corners and uneven
+    const OVALS: [(i32, i32, i32, i32); 8] = [
+        (0, 0, 0, 0),
+        (0, 0, 1, 1),
+        (0, 0, 31, 0),
+        (0, 0, 0, 17),
+        (0, 0, 10, 3),
+        (5, 2, -3, 9),
+        (0, 0, 6, 7),
+        (-20, -4, 43, 25),
+    ];
+
+    /// Lists the pixels as runs per row, one row per line
+    fn write_rows(out: &mut String, pixels: &BTreeSet<(i32, i32)>) {
+        let mut row = None;
+        let mut run: Option<(i32, i32)> = None;
+        for &(y, x) in pixels {
+            if row != Some(y) || run.is_some_and(|(_, end)| end + 1 != x) {
+                if let Some((start, end)) = run {
+                    write!(out, " {}..{}", start, end).unwrap();
+                }
+                run = None;
+            }
+            if row != Some(y) {
+                write!(out, "\n{}:", y).unwrap();
+                row = Some(y);
+            }
+            run = Some((run.map_or(x, |(start, _)| start), x));
+        }
+        if let Some((start, end)) = run {
+            write!(out, " {}..{}", start, end).unwrap();
+        }
+        out.push('\n');
+    }
+
+    fn circles(filled: bool) -> String {
+        let mut out = String::new();
+        for r in 0..=64 {
+            let mut pixels = BTreeSet::new();
+            if filled {
+                circle_spans(r, |left, right, y| {
+                    pixels.extend((left..=right).map(|x| (y, x)));
+                });
+            } else {
+                circle_points(r, |x, y| {
+                    pixels.insert((y, x));
+                });
+            }
+            write!(out, "r={}", r).unwrap();
+            write_rows(&mut out, &pixels);
+        }
+        out
+    }
+
+    fn ovals(filled: bool) -> String {
+        let mut out = String::new();
+        for &(x0, y0, x1, y1) in &OVALS {
+            let mut pixels = BTreeSet::new();
+            oval_spans(x0, y0, x1, y1, |left, right, y| {
+                if filled {
+                    pixels.extend((left..=right).map(|x| (y, x)));
+                } else {
+                    pixels.insert((y, left));
+                    pixels.insert((y, right));
+                }
+            });
+            write!(out, "{},{},{},{}", x0, y0, x1, y1).unwrap();
+            write_rows(&mut out, &pixels);
+        }
+        out
+    }
+
+    #[test]
+    #[ignore = "needs the PICO-8 dump from tests/fixtures/gfx/pico8/capture.p8"]
+    fn circ_matches_pico8() {
+        assert_pico8("circ.txt", &circles(false));
+    }
+
+    #[test]
+    #[ignore = "needs the PICO-8 dump from tests/fixtures/gfx/pico8/capture.p8"]
+    fn circfill_matches_pico8() {
+        assert_pico8("circfill.txt", &circles(true));
+    }
+
+    #[test]
+    #[ignore = "needs the PICO-8 dump from tests/fixtures/gfx/pico8/capture.p8"]
+    fn oval_matches_pico8() {
+        assert_pico8("oval.txt", &ovals(false));
+    }
+
+    #[test]
+    #[ignore = "needs the PICO-8 dump from tests/fixtures/gfx/pico8/capture.p8"]
+    fn ovalfill_matches_pico8() {
+        assert_pico8("ovalfill.txt", &ovals(true));
+    }
+
+    #[test]
+    fn circfill_covers_circ() {
+        for r in 0..=64 {
+            let mut filled = BTreeSet::new();
+            circle_spans(r, |left, right, y| {
+                filled.extend((left..=right).map(|x| (x, y)));
+            });
+            circle_points(r, |x, y| {
+                assert!(filled.contains(&(x, y)), "r={} ({}, {})", r, x, y)
+            });
+        }
+    }
+
+    /// Draws `string` at (2, 3) on a cleared console, returns the memory and where the text ended
+    fn render_text(string: &[u8]) -> (Box<[u8; 0x8000]>, (i32, i32)) {
+        let mem = Mutex::new([0u8; 0x8000]);
+        let mut mem = mem.lock().unwrap();
+        draw_state::reset(Some(&mut mem));
+        let end = draw_text(&mut mem, string, 2, 3, 7, true);
+        (Box::new(*mem), end)
+    }
+
+    fn assert_same_text(string: &[u8], expected: &[u8]) {
+        let (mem, end) = render_text(string);
+        let (expected_mem, expected_end) = render_text(expected);
+        assert_eq!(end, expected_end, "{:?}", String::from_utf8_lossy(string));
+        assert!(
+            mem[..] == expected_mem[..],
+            "{:?}",
+            String::from_utf8_lossy(string)
+        );
+    }
+
+    #[test]
+    fn null_ends_text() {
+        assert!(render_text(b"ab").0[0x6000..] != render_text(b"abcd").0[0x6000..]);
+        assert_same_text(b"ab\0cd", b"ab");
+        assert_same_text(b"ab\0\ncd", b"ab");
+    }
+
+    #[test]
+    fn unsupported_commands_skip_their_parameters() {
+        assert_same_text(b"a\x06d3b", b"ab");
+        assert_same_text(b"a\x06j12b", b"ab");
+        assert_same_text(b"a\x06x4\x06y5b", b"ab");
+        assert_same_text(b"a\x06:0103070f1f3f7fffb", b"ab");
+        assert_same_text(b"a\x06.\x01\x02\x03\x04\x05\x06\x07\x08b", b"ab");
+        assert_same_text(b"a\x06@5f000003xyzb", b"ab");
+        assert_same_text(b"a\x06!bc", b"a");
+        assert_same_text(b"a\x06gb", b"ab");
+    }
+    #[test]
+    fn measuring_matches_drawing() {
+        let wrapped = [b'a'; 40];
+        let strings: [&[u8]; 5] = [
+            b"one\ntwo\nthree",
+            &wrapped,
+            b"\x06ttall\nlines",
+            b"\x0ecustom\nfont",
+            b"\x06cclear",
+        ];
+        for string in strings {
+            let layout = |draw| {
+                let mem = Mutex::new([0u8; 0x8000]);
+                let mut mem = mem.lock().unwrap();
+                draw_state::reset(Some(&mut mem));
+                mem[0x5600] = 4;
+                mem[0x5601] = 4;
+                mem[0x5602] = 10;
+                let before = *mem;
+                let end = draw_text(&mut mem, string, 2, 3, 7, draw);
+                (end, mem[..] == before[..])
+            };
+            let (measured, untouched) = layout(false);
+            let (drawn, _) = layout(true);
+            assert!(untouched, "{:?}", String::from_utf8_lossy(string));
+            assert_eq!(measured, drawn, "{:?}", String::from_utf8_lossy(string));
+        }
+
+        assert_eq!(render_text(&wrapped).1 .1, 3 + 6 * 2);
+        assert_eq!(render_text(b"\x06ttall\nlines").1 .1, 3 + 12 * 2);
+    }
+
+    #[test]
+    fn cls_resets_print_cursor() {
+        let mem = Mutex::new([0u8; 0x8000]);
+        let mut mem = mem.lock().unwrap();
+        draw_state::reset(Some(&mut mem));
+        draw_state::set_print_cursor(Some(&mut mem), Some(10), Some(42));
+        clear_screen(&mut mem, 1);
+        assert_eq!(draw_state::get_print_cursor(Some(&mem)), (0, 0));
+        assert!(mem[0x6000..].iter().all(|&byte| byte == 0x11));
+    }
+
+    #[test]
+    fn pen_color_keeps_secondary_color() {
+        let mem = Mutex::new([0u8; 0x8000]);
+        let mut mem = mem.lock().unwrap();
+        draw_state::set_pen_color(Some(&mut mem), 0x1c);
+        assert_eq!(draw_state::get_pen_color(Some(&mem)), 0x1c);
+    }
+
+    #[test]
+    fn print_cursor_is_signed() {
+        let mem = Mutex::new([0u8; 0x8000]);
+        let mut mem = mem.lock().unwrap();
+        draw_state::set_print_cursor(Some(&mut mem), Some(-5), Some(300));
+        assert_eq!(draw_state::get_print_cursor(Some(&mem)), (-5, 127));
+        draw_state::set_print_cursor(Some(&mut mem), Some(-300), Some(-1));
+        assert_eq!(draw_state::get_print_cursor(Some(&mem)), (-128, -1));
+    }
+}
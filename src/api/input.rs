@@ -1,75 +1,257 @@
-use crate::{CONFIG, KEYSTATE_FRAME, KEYSTATE_FRAME_FIFO, KEYSTATE_HELD};
+use crate::config::KeyBindings;
+use crate::controller::PLAYER_COUNT;
+use crate::{
+    BUTTON_HOLD, CONFIG, FRAME_STATS, KEYSTATE_FRAME_FIFO, KEYSTATE_HELD, MEM, MOUSE, PAD_HELD,
+    TEXT_INPUT,
+};
 use sdl2::keyboard::Scancode;
+use std::collections::HashSet;
+
+/// Mouse state in console coordinates, read by carts in devkit mode
+#[derive(Default)]
+pub struct MouseState {
+    pub x: i32,
+    pub y: i32,
+    /// Bit 0 left, bit 1 right, bit 2 middle
+    pub buttons: i32,
+    /// Wheel movement during the last frame, positive is up
+    pub wheel: i32,
+    /// Movement during the last frame in window pixels, used when the pointer is locked
+    pub rel_x: i32,
+    pub rel_y: i32,
+}
+
+/// Checks if devkit mode (keyboard and mouse input for carts) is enabled by bit 0 of 0x5f2d
+pub fn devkit_mode() -> bool {
+    MEM.lock().unwrap()[0x5f2d] & 0b1 != 0
+}
+
+/// Checks if the pointer should be locked for relative mouse motion, bit 2 of 0x5f2d
+pub fn mouse_lock() -> bool {
+    let mem = MEM.lock().unwrap();
+    mem[0x5f2d] & 0b1 != 0 && mem[0x5f2d] & 0b100 != 0
+}
+
+/// Reads a field of the mouse state, 0 outside devkit mode
+pub fn mouse(field: impl Fn(&MouseState) -> i32) -> i32 {
+    match devkit_mode() {
+        true => field(&MOUSE.lock().unwrap()),
+        false => 0,
+    }
+}
+
+/// Checks if typed text is waiting to be read in devkit mode
+pub fn key_pending() -> bool {
+    devkit_mode() && !TEXT_INPUT.lock().unwrap().is_empty()
+}
+
+/// Takes the next typed P8SCII character in devkit mode
+pub fn next_key() -> Option<u8> {
+    if !devkit_mode() {
+        return None;
+    }
+
+    let mut text_input = TEXT_INPUT.lock().unwrap();
+    if !text_input.is_empty() {
+        Some(text_input.remove(0))
+    } else {
+        None
+    }
+}
 
 pub fn key() -> i32 {
     let mut keystate_fifo = KEYSTATE_FRAME_FIFO.lock().unwrap();
-    if keystate_fifo.len() > 0 {
+    if !keystate_fifo.is_empty() {
         keystate_fifo.remove(0) as i32
     } else {
         0
     }
 }
+
+/// Default frames a button is held before `btnp` repeats and between repeats, overridden by
+/// 0x5f5c/0x5f5d (255 in 0x5f5c disables repeating). Both are in 30fps frames and scaled to the
+/// cart's frame rate
+const BTNP_DELAY: u32 = 15;
+const BTNP_REPEAT: u32 = 4;
+
+fn is_valid_button(i: i32, p: i32) -> bool {
+    (0..6).contains(&i) && (0..PLAYER_COUNT as i32).contains(&p)
+}
+
+/// Checks if button `i` of player `p` is held on the keyboard in `keys` or a controller in `pads`
+fn button_held(
+    bindings: &KeyBindings,
+    i: i32,
+    p: i32,
+    keys: &HashSet<Scancode>,
+    pads: &[u8; PLAYER_COUNT],
+) -> bool {
+    let key_held = bindings
+        .players
+        .get(p as usize)
+        .and_then(|player| player.button(i))
+        .and_then(Scancode::from_i32)
+        .is_some_and(|scancode| keys.contains(&scancode));
+
+    key_held || pads[p as usize] & (1 << i) != 0
+}
+
+/// Counts how many frames each button of each player has been held for `btnp`, called once per
+/// frame so OS key repeat doesn't affect it. Buttons in `pressed_keys`/`pressed_pads` went down
+/// during the frame and count as newly pressed even if they were released again before the poll
+pub fn update_button_hold(
+    bindings: &KeyBindings,
+    keys: &HashSet<Scancode>,
+    pads: &[u8; PLAYER_COUNT],
+    pressed_keys: &HashSet<Scancode>,
+    pressed_pads: &[u8; PLAYER_COUNT],
+) {
+    let mut button_hold = BUTTON_HOLD.lock().unwrap();
+    for (p, player_hold) in button_hold.iter_mut().enumerate() {
+        for (i, frames) in player_hold.iter_mut().enumerate() {
+            let (i, p) = (i as i32, p as i32);
+            *frames = if button_held(bindings, i, p, pressed_keys, pressed_pads) {
+                1
+            } else if button_held(bindings, i, p, keys, pads) {
+                frames.saturating_add(1)
+            } else {
+                0
+            };
+        }
+    }
+}
+
+/// Packs the state of every button of players 0 and 1 into bits 0-5 and 8-13
+fn button_bitfield(state: impl Fn(i32, i32) -> i32) -> i32 {
+    let mut bitfield = 0;
+    for p in 0..2 {
+        for i in 0..6 {
+            bitfield |= state(i, p) << (i + (p * 8));
+        }
+    }
+    bitfield
+}
+
 pub fn btn(i: i32, p: i32) -> i32 {
-    let keystate_held = KEYSTATE_HELD.lock().unwrap();
-    if p != 0 && p != 1 {
-        panic!("Invalid player number {}", p);
+    if !is_valid_button(i, p) {
+        return 0;
     }
 
     let config = CONFIG.lock().unwrap();
-    let keycode;
-    if p == 0 {
-        keycode = match i {
-            0 => config.keys.player1.left,
-            1 => config.keys.player1.right,
-            2 => config.keys.player1.up,
-            3 => config.keys.player1.down,
-            4 => config.keys.player1.o,
-            5 => config.keys.player1.x,
-            _ => panic!("Invalid keycode {}", i),
-        };
-    } else {
-        keycode = match i {
-            0 => config.keys.player2.left,
-            1 => config.keys.player2.right,
-            2 => config.keys.player2.up,
-            3 => config.keys.player2.down,
-            4 => config.keys.player2.o,
-            5 => config.keys.player2.x,
-            _ => panic!("Invalid keycode {}", i),
-        };
+    let keystate_held = KEYSTATE_HELD.lock().unwrap();
+    let pad_held = PAD_HELD.lock().unwrap();
+    button_held(&config.keys, i, p, &keystate_held, &pad_held) as i32
+}
+
+/// True on the frame a button is pressed, then after it is held for the repeat delay and every
+/// repeat interval after that
+pub fn btnp(i: i32, p: i32) -> i32 {
+    if !is_valid_button(i, p) {
+        return 0;
     }
 
-    keystate_held.contains(&Scancode::from_i32(keycode).unwrap()) as i32
+    let frames = BUTTON_HOLD.lock().unwrap()[p as usize][i as usize];
+    let (delay, repeat) = {
+        let mem = MEM.lock().unwrap();
+        (mem[0x5f5c], mem[0x5f5d])
+    };
+    let delay = match delay {
+        0 => BTNP_DELAY,
+        delay => delay as u32,
+    };
+    let repeat = match repeat {
+        0 => BTNP_REPEAT,
+        repeat => repeat as u32,
+    };
+
+    let scale = match FRAME_STATS.lock().unwrap().target_fps {
+        0 => 1.0,
+        fps => fps as f32 / 30.0,
+    };
+    let scaled = |frames: u32| ((frames as f32 * scale).round() as u32).max(1);
+
+    let pressed = match frames {
+        1 => true,
+        _ if delay == 255 => false,
+        frames => {
+            let (delay, repeat) = (scaled(delay), scaled(repeat));
+            frames > delay && (frames - 1 - delay) % repeat == 0
+        }
+    };
+    pressed as i32
 }
-pub fn btnp(i: i32, p: i32) -> i32 {
-    let keystate_frame = KEYSTATE_FRAME.lock().unwrap();
-    if p != 0 && p != 1 {
-        panic!("Invalid player number {}", p);
+
+/// `btn` for every button of players 0 and 1 as a bitfield
+pub fn btn_bitfield() -> i32 {
+    button_bitfield(btn)
+}
+
+/// `btnp` for every button of players 0 and 1 as a bitfield
+pub fn btnp_bitfield() -> i32 {
+    button_bitfield(btnp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{btnp, update_button_hold};
+    use crate::config::Config;
+    use crate::controller::PLAYER_COUNT;
+    use crate::FRAME_STATS;
+    use sdl2::keyboard::Scancode;
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    lazy_static! {
+        /// The button hold counters and frame stats are global, run the tests one at a time
+        static ref INPUT: Mutex<()> = Mutex::new(());
     }
 
-    let config = CONFIG.lock().unwrap();
-    let keycode;
-    if p == 0 {
-        keycode = match i {
-            0 => config.keys.player1.left,
-            1 => config.keys.player1.right,
-            2 => config.keys.player1.up,
-            3 => config.keys.player1.down,
-            4 => config.keys.player1.o,
-            5 => config.keys.player1.x,
-            _ => panic!("Invalid keycode {}", i),
-        };
-    } else {
-        keycode = match i {
-            0 => config.keys.player2.left,
-            1 => config.keys.player2.right,
-            2 => config.keys.player2.up,
-            3 => config.keys.player2.down,
-            4 => config.keys.player2.o,
-            5 => config.keys.player2.x,
-            _ => panic!("Invalid keycode {}", i),
-        };
+    /// Holds O of player 0 for `frames` frames at `fps` and returns the frames btnp was true on
+    fn hold_presses(fps: u32, frames: u32) -> Vec<u32> {
+        FRAME_STATS.lock().unwrap().target_fps = fps;
+        let bindings = Config::new().keys;
+        let held: HashSet<Scancode> = [Scancode::from_i32(bindings.players[0].o).unwrap()]
+            .iter()
+            .copied()
+            .collect();
+        let none = HashSet::new();
+        let no_pads = [0; PLAYER_COUNT];
+
+        update_button_hold(&bindings, &none, &no_pads, &none, &no_pads);
+        (1..=frames)
+            .filter(|_| {
+                update_button_hold(&bindings, &held, &no_pads, &none, &no_pads);
+                btnp(4, 0) != 0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn repeat_scales_with_frame_rate() {
+        let _lock = INPUT.lock().unwrap();
+        assert_eq!(hold_presses(30, 24), vec![1, 16, 20, 24]);
+        assert_eq!(hold_presses(60, 48), vec![1, 31, 39, 47]);
+        FRAME_STATS.lock().unwrap().target_fps = 0;
     }
 
-    keystate_frame.contains(&Scancode::from_i32(keycode).unwrap()) as i32
+    #[test]
+    fn tap_within_a_frame_is_a_press() {
+        let _lock = INPUT.lock().unwrap();
+        let bindings = Config::new().keys;
+        let o = Scancode::from_i32(bindings.players[0].o).unwrap();
+        let none = HashSet::new();
+        let no_pads = [0; PLAYER_COUNT];
+        let tapped: HashSet<Scancode> = [o].iter().copied().collect();
+        let mut tapped_pads = [0; PLAYER_COUNT];
+        tapped_pads[1] = 0b1_0000;
+
+        // Pressed and released between two polls, so neither is held
+        update_button_hold(&bindings, &none, &no_pads, &tapped, &tapped_pads);
+        assert_eq!(btnp(4, 0), 1);
+        assert_eq!(btnp(4, 1), 1);
+
+        update_button_hold(&bindings, &none, &no_pads, &none, &no_pads);
+        assert_eq!(btnp(4, 0), 0);
+        assert_eq!(btnp(4, 1), 0);
+    }
 }
@@ -27,7 +27,7 @@ pub fn bxor(x: i32, y: i32) -> i32 {
 }
 
 pub fn cos(x: f32) -> f32 {
-    ((std::f32::consts::PI * 2.0) / x).cos().max(-1.0).min(1.0)
+    ((std::f32::consts::PI * 2.0) / x).cos().clamp(-1.0, 1.0)
 }
 
 pub fn flr(x: f32) -> f32 {
@@ -105,7 +105,7 @@ pub fn shr(x: i32, y: i32) -> i32 {
 }
 
 pub fn sin(x: f32) -> f32 {
-    (((std::f32::consts::PI * 2.0) * -x).sin()).max(-1.0).min(1.0)
+    ((std::f32::consts::PI * 2.0) * -x).sin().clamp(-1.0, 1.0)
 }
 
 pub fn sqrt(x: i32) -> f32 {
@@ -1,6 +1,48 @@
-use crate::{cart::Cart, config::Config, CART, CART_TO_LOAD, TIME};
+use byteorder::{ByteOrder, LittleEndian};
+use chrono::{Datelike, Local, Timelike, Utc};
+
+use crate::{
+    api, cart::Cart, config::Config, CART, CART_DATA_ID, CART_TO_LOAD, FRAME_STATS, MEM,
+    MENU_ITEMS, SYNTH, TIME,
+};
+
+const CART_DATA_BASE: usize = 0x5e00;
+const CART_DATA_SIZE: usize = 0x100;
+
+/// Value returned by `stat`, PICO-8 returns some queries as booleans or strings
+pub enum StatValue {
+    Number(f32),
+    Bool(bool),
+    String(Vec<u8>),
+}
+
+impl StatValue {
+    /// Converts to a number for runtimes without booleans or strings, a string becomes its first
+    /// character
+    pub fn as_f32(&self) -> f32 {
+        match self {
+            StatValue::Number(val) => *val,
+            StatValue::Bool(val) => *val as i32 as f32,
+            StatValue::String(val) => val.first().map_or(0.0, |ch| *ch as f32),
+        }
+    }
+}
+
+/// Measurements of the last frame, updated by the main loop
+#[derive(Default)]
+pub struct FrameStats {
+    /// Bytes used by the cart's runtime
+    pub memory: usize,
+    /// Fraction of the frame spent in the cart's update and draw
+    pub cpu: f32,
+    /// Fraction of the frame spent on everything, including presenting it
+    pub system_cpu: f32,
+    pub fps: u32,
+    pub target_fps: u32,
+}
 
 pub fn exit() {
+    flush_cartdata();
     std::process::exit(0); // lol
 }
 
@@ -22,15 +64,188 @@ pub fn load(str: String) {
     let str = str.replace("..", "");
     let path = std::path::Path::new(&Config::get_config_dir_or_create().unwrap()).join(str);
     if !path.is_file() {
-        panic!("{} is not a file!", path.to_str().unwrap().to_string());
+        panic!("{} is not a file!", path.to_str().unwrap());
     }
 
     let path = path.to_str().unwrap().to_string();
-    *CART.lock().unwrap() = Some(Cart::load(&path));
+    *CART.lock().unwrap() = Some(<dyn Cart>::load(&path));
     *CART_TO_LOAD.lock().unwrap() = true;
     println!("Loaded {}", path);
 }
 
+/// Reads a byte of console memory, addresses outside it return 0
+pub fn peek(addr: i32) -> i32 {
+    match addr {
+        0..=0x7fff => MEM.lock().unwrap()[addr as usize] as i32,
+        _ => 0,
+    }
+}
+
+/// Writes a byte of console memory, addresses outside it are ignored
+pub fn poke(addr: i32, val: i32) {
+    if let 0..=0x7fff = addr {
+        MEM.lock().unwrap()[addr as usize] = val as u8;
+    }
+}
+
+/// A pause menu entry added by the cart, `callback` identifies the function to the runtime
+#[derive(Clone)]
+pub struct MenuItem {
+    pub label: String,
+    pub callback: i32,
+}
+
+/// Sets pause menu entry `index` (1-5) to `label`, or removes it if `label` is `None`
+pub fn menuitem(index: i32, label: Option<String>, callback: i32) {
+    if let 1..=5 = index {
+        MENU_ITEMS.lock().unwrap()[index as usize - 1] =
+            label.map(|label| MenuItem { label, callback });
+    }
+}
+
+pub fn clear_menu_items() {
+    *MENU_ITEMS.lock().unwrap() = Default::default();
+}
+
+fn cartdata_path(id: &str) -> Option<std::path::PathBuf> {
+    match Config::get_config_dir_or_create() {
+        Some(config_dir) => Some(
+            std::path::Path::new(&config_dir)
+                .join("cartdata")
+                .join(format!("{}.dat", id)),
+        ),
+        None => {
+            println!("No config directory, cartdata `{}` won't be saved", id);
+            None
+        }
+    }
+}
+
+/// Maps the persistent data saved under `id` to 0x5e00..0x5eff, returns true if there was data
+/// to load. `id` may only use lowercase letters, digits and underscores, up to 64 of them
+pub fn cartdata(id: String) -> bool {
+    let valid = |ch: char| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_';
+    if id.is_empty() || id.len() > 64 || !id.chars().all(valid) {
+        println!("Invalid cartdata id `{}`, ignoring", id);
+        return false;
+    }
+
+    // Save what another id has before it is replaced
+    flush_cartdata();
+
+    let data = cartdata_path(&id).and_then(|path| std::fs::read(path).ok());
+    let mut mem = MEM.lock().unwrap();
+    let cart_data = &mut mem[CART_DATA_BASE..CART_DATA_BASE + CART_DATA_SIZE];
+    cart_data.fill(0);
+    if let Some(data) = data.as_ref() {
+        let len = data.len().min(CART_DATA_SIZE);
+        cart_data[..len].copy_from_slice(&data[..len]);
+    }
+    drop(mem);
+
+    *CART_DATA_ID.lock().unwrap() = Some(id);
+    data.is_some()
+}
+
+/// Writes the cart data to its file if `cartdata` has been called
+pub fn flush_cartdata() {
+    let id = match CART_DATA_ID.lock().unwrap().clone() {
+        Some(id) => id,
+        None => return,
+    };
+
+    let path = match cartdata_path(&id) {
+        Some(path) => path,
+        None => return,
+    };
+    let data = MEM.lock().unwrap()[CART_DATA_BASE..CART_DATA_BASE + CART_DATA_SIZE].to_vec();
+    if let Err(why) = std::fs::create_dir_all(path.parent().unwrap()) {
+        println!("Unable to create cartdata directory. Reason: {}", why);
+        return;
+    }
+    if let Err(why) = std::fs::write(&path, data) {
+        println!("Unable to save cartdata `{}`. Reason: {}", id, why);
+    }
+}
+
+/// Flushes the cart data and stops saving it, used when another cart is loaded
+pub fn close_cartdata() {
+    flush_cartdata();
+    *CART_DATA_ID.lock().unwrap() = None;
+}
+
+/// Reads number `idx` (0-63) of the cart data, stored as 16.16 fixed point like PICO-8
+pub fn dget(idx: i32) -> f32 {
+    match idx {
+        0..=63 => {
+            let addr = CART_DATA_BASE + idx as usize * 4;
+            let mem = MEM.lock().unwrap();
+            LittleEndian::read_i32(&mem[addr..addr + 4]) as f32 / 65536.0
+        }
+        _ => 0.0,
+    }
+}
+
+/// Sets number `idx` (0-63) of the cart data and saves it
+pub fn dset(idx: i32, val: f32) {
+    if let 0..=63 = idx {
+        let addr = CART_DATA_BASE + idx as usize * 4;
+        let fixed = (val * 65536.0) as i32;
+        LittleEndian::write_i32(&mut MEM.lock().unwrap()[addr..addr + 4], fixed);
+        flush_cartdata();
+    }
+}
+
 pub fn time() -> f32 {
     *(TIME.lock().unwrap())
 }
+
+/// Year, month, day, hour, minute and second for `field` 0-5, 0 for anything else
+fn date_field<T: Datelike + Timelike>(date: &T, field: i32) -> f32 {
+    match field {
+        0 => date.year() as f32,
+        1 => date.month() as f32,
+        2 => date.day() as f32,
+        3 => date.hour() as f32,
+        4 => date.minute() as f32,
+        5 => date.second() as f32,
+        _ => 0.0,
+    }
+}
+
+/// Queries system state, unknown queries return 0
+pub fn stat(n: i32) -> StatValue {
+    match n {
+        0 => StatValue::Number(FRAME_STATS.lock().unwrap().memory as f32 / 1024.0),
+        1 => StatValue::Number(FRAME_STATS.lock().unwrap().cpu),
+        2 => StatValue::Number(FRAME_STATS.lock().unwrap().system_cpu),
+        7 => StatValue::Number(FRAME_STATS.lock().unwrap().fps as f32),
+        8 => StatValue::Number(FRAME_STATS.lock().unwrap().target_fps as f32),
+        // 16-26 are the older names of 46-56
+        16..=26 => stat(n + 30),
+        46..=49 => StatValue::Number(SYNTH.lock().unwrap().channel_sfx(n as usize - 46) as f32),
+        50..=53 => StatValue::Number(SYNTH.lock().unwrap().channel_note(n as usize - 50) as f32),
+        54..=56 => {
+            let status = SYNTH.lock().unwrap().music_status();
+            StatValue::Number(match (status, n) {
+                (Some((pattern, _, _)), 54) => pattern as f32,
+                (Some((_, played, _)), 55) => played as f32,
+                (Some((_, _, ticks)), _) => ticks as f32,
+                (None, 54) => -1.0,
+                (None, _) => 0.0,
+            })
+        }
+        57 => StatValue::Bool(SYNTH.lock().unwrap().music_status().is_some()),
+        30 => StatValue::Bool(api::input::key_pending()),
+        31 => StatValue::String(api::input::next_key().into_iter().collect()),
+        32 => StatValue::Number(api::input::mouse(|mouse| mouse.x) as f32),
+        33 => StatValue::Number(api::input::mouse(|mouse| mouse.y) as f32),
+        34 => StatValue::Number(api::input::mouse(|mouse| mouse.buttons) as f32),
+        36 => StatValue::Number(api::input::mouse(|mouse| mouse.wheel) as f32),
+        38 => StatValue::Number(api::input::mouse(|mouse| mouse.rel_x) as f32),
+        39 => StatValue::Number(api::input::mouse(|mouse| mouse.rel_y) as f32),
+        80..=85 => StatValue::Number(date_field(&Utc::now(), n - 80)),
+        90..=95 => StatValue::Number(date_field(&Local::now(), n - 90)),
+        _ => StatValue::Number(0.0),
+    }
+}
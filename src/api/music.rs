@@ -1,7 +1,13 @@
+use crate::{MEM, SYNTH};
+
 pub fn music(n: i32, fadems: i32, channelmask: u32) {
-    //Stub
+    let mut synth = SYNTH.lock().unwrap();
+    let mem = MEM.lock().unwrap();
+    synth.music(&mem, n, fadems, channelmask as u8);
 }
 
 pub fn sfx(n: i32, channel: i32, offset: i32, length: i32) {
-    //Stub
+    let mut synth = SYNTH.lock().unwrap();
+    let mem = MEM.lock().unwrap();
+    synth.sfx(&mem, n, channel, offset, length);
 }
@@ -0,0 +1,204 @@
+use byteorder::{LittleEndian, WriteBytesExt};
+use std::fs;
+
+use crate::cart::Cart;
+use crate::synth::{Synth, SAMPLE_RATE};
+use crate::MEM;
+
+// Upper bound for carts whose audio never ends or loops
+const MAX_EXPORT_SECONDS: u32 = 600;
+
+enum ExportTarget {
+    Sfx(i32),
+    Music(i32),
+}
+
+fn usage() -> ! {
+    println!(
+        "Usage: `{} audio-export <cart> (--sfx <n> | --music <n>) [--out <file.wav>]`",
+        std::env::args().next().unwrap()
+    );
+    std::process::exit(1);
+}
+
+fn parse_idx(val: Option<&String>) -> i32 {
+    match val.map(|v| v.parse::<i32>()) {
+        Some(Ok(idx)) if (0..64).contains(&idx) => idx,
+        _ => usage(),
+    }
+}
+
+fn wav_bytes(samples: &[f32]) -> std::io::Result<Vec<u8>> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut out_buf: Vec<u8> = Vec::with_capacity(44 + data_len as usize);
+
+    out_buf.extend_from_slice(b"RIFF");
+    out_buf.write_u32::<LittleEndian>(36 + data_len)?;
+    out_buf.extend_from_slice(b"WAVE");
+
+    out_buf.extend_from_slice(b"fmt ");
+    out_buf.write_u32::<LittleEndian>(16)?;
+    out_buf.write_u16::<LittleEndian>(1)?; // PCM
+    out_buf.write_u16::<LittleEndian>(1)?; // Mono
+    out_buf.write_u32::<LittleEndian>(SAMPLE_RATE)?;
+    out_buf.write_u32::<LittleEndian>(SAMPLE_RATE * 2)?;
+    out_buf.write_u16::<LittleEndian>(2)?;
+    out_buf.write_u16::<LittleEndian>(16)?;
+
+    out_buf.extend_from_slice(b"data");
+    out_buf.write_u32::<LittleEndian>(data_len)?;
+    for sample in samples {
+        out_buf.write_i16::<LittleEndian>((sample * i16::MAX as f32) as i16)?;
+    }
+
+    Ok(out_buf)
+}
+
+pub fn write_wav(path: &str, samples: &[f32]) -> std::io::Result<()> {
+    fs::write(path, wav_bytes(samples)?)
+}
+
+/// Renders `target` until it ends or loops back
+fn render(mem: &[u8; 0x8000], target: &ExportTarget) -> Vec<f32> {
+    let mut synth = Synth::new();
+    match *target {
+        ExportTarget::Sfx(n) => synth.sfx(mem, n, 0, 0, 0),
+        ExportTarget::Music(n) => synth.music(mem, n, 0, 0),
+    }
+
+    let mut samples: Vec<f32> = Vec::new();
+    let mut sample = [0.0f32; 1];
+    while synth.is_playing() && samples.len() < (MAX_EXPORT_SECONDS * SAMPLE_RATE) as usize {
+        synth.render(mem, &mut sample);
+        if synth.has_looped() {
+            break;
+        }
+        samples.push(sample[0]);
+    }
+
+    samples
+}
+
+/// Renders an sfx or music pattern of a cart to a WAV file without opening an audio device
+pub fn run(args: &[String]) {
+    let mut cart_path: Option<String> = None;
+    let mut target: Option<ExportTarget> = None;
+    let mut out_path: Option<String> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--sfx" => target = Some(ExportTarget::Sfx(parse_idx(args.next()))),
+            "--music" => target = Some(ExportTarget::Music(parse_idx(args.next()))),
+            "--out" | "-o" => out_path = Some(args.next().unwrap_or_else(|| usage()).clone()),
+            _ => cart_path = Some(arg.clone()),
+        }
+    }
+
+    let (cart_path, target) = match (cart_path, target) {
+        (Some(cart_path), Some(target)) => (cart_path, target),
+        _ => usage(),
+    };
+
+    let cart = <dyn Cart>::load(&cart_path);
+    let out_path = out_path.unwrap_or_else(|| {
+        let stem = match cart.name().rfind('.') {
+            Some(idx) => cart.name()[..idx].to_string(),
+            None => cart.name(),
+        };
+        match target {
+            ExportTarget::Sfx(n) => format!("{}_sfx{}.wav", stem, n),
+            ExportTarget::Music(n) => format!("{}_music{}.wav", stem, n),
+        }
+    });
+
+    let samples = render(&MEM.lock().unwrap(), &target);
+
+    if let Err(why) = write_wav(&out_path, &samples) {
+        panic!("Unable to write {}, reason: {}", out_path, why);
+    }
+
+    println!(
+        "Wrote {} ({:.2}s)",
+        out_path,
+        samples.len() as f32 / SAMPLE_RATE as f32
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render, wav_bytes, ExportTarget};
+    use crate::utils::assert_fixture;
+
+    const PITCHES: [u8; 4] = [24, 28, 31, 36];
+
+    /// Writes a four note sfx to `mem` with the same waveform, volume and effect on every note
+    fn set_sfx(mem: &mut [u8; 0x8000], sfx: usize, waveform: u8, effect: u8, custom: bool) {
+        let addr = 0x3200 + sfx * 68;
+        for (idx, pitch) in PITCHES.iter().enumerate() {
+            mem[addr + idx * 2] = pitch | ((waveform & 0b11) << 6);
+            mem[addr + idx * 2 + 1] =
+                (waveform >> 2) | (5 << 1) | (effect << 4) | ((custom as u8) << 7);
+        }
+        // Speed, then a loop start with no loop end to end the sfx after the notes
+        mem[addr + 65] = 4;
+        mem[addr + 66] = PITCHES.len() as u8;
+    }
+
+    fn render_wav(mem: &[u8; 0x8000], target: ExportTarget) -> Vec<u8> {
+        wav_bytes(&render(mem, &target)).unwrap()
+    }
+
+    #[test]
+    fn waveforms_match_fixtures() {
+        for waveform in 0..8 {
+            let mut mem = [0u8; 0x8000];
+            set_sfx(&mut mem, 0, waveform, 0, false);
+            let wav = render_wav(&mem, ExportTarget::Sfx(0));
+            assert_fixture(&format!("synth/waveform{}.wav", waveform), &wav);
+        }
+    }
+
+    #[test]
+    fn effects_match_fixtures() {
+        for effect in 1..8 {
+            let mut mem = [0u8; 0x8000];
+            set_sfx(&mut mem, 0, 3, effect, false);
+            // Slow enough for the fast arpeggio to step within a note
+            mem[0x3200 + 65] = 8;
+            let wav = render_wav(&mem, ExportTarget::Sfx(0));
+            assert_fixture(&format!("synth/effect{}.wav", effect), &wav);
+        }
+    }
+
+    #[test]
+    fn custom_instrument_matches_fixture() {
+        let mut mem = [0u8; 0x8000];
+        set_sfx(&mut mem, 2, 5, 0, false);
+        set_sfx(&mut mem, 0, 2, 0, true);
+        let wav = render_wav(&mem, ExportTarget::Sfx(0));
+        assert_fixture("synth/custom.wav", &wav);
+    }
+
+    #[test]
+    fn looping_sfx_matches_fixture() {
+        let mut mem = [0u8; 0x8000];
+        set_sfx(&mut mem, 0, 1, 0, false);
+        mem[0x3200 + 66] = 1;
+        mem[0x3200 + 67] = 3;
+        let wav = render_wav(&mem, ExportTarget::Sfx(0));
+        assert_fixture("synth/loop.wav", &wav);
+    }
+
+    #[test]
+    fn music_matches_fixture() {
+        let mut mem = [0u8; 0x8000];
+        set_sfx(&mut mem, 0, 0, 0, false);
+        set_sfx(&mut mem, 1, 4, 2, false);
+        set_sfx(&mut mem, 2, 6, 5, false);
+        // Pattern 0 plays sfx 0 and 1, pattern 1 plays sfx 2 and stops
+        mem[0x3100..0x3108].copy_from_slice(&[0, 1, 0x40, 0x40, 2, 0x40, 0xc0, 0x40]);
+        let wav = render_wav(&mem, ExportTarget::Music(0));
+        assert_fixture("synth/music.wav", &wav);
+    }
+}
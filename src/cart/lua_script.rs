@@ -11,7 +11,7 @@ pub struct LuaScript {
 
 impl LuaScript {
     pub fn new(path: &String) -> Self {
-        let metadata = match fs::metadata(&path) {
+        let metadata = match fs::metadata(path) {
             Ok(md) => md,
             Err(why) => panic!("Unable to open file {}, reason: {}", path, why),
         };
@@ -35,7 +35,7 @@ impl LuaScript {
             None => path.clone(),
         };
 
-        let script = match fs::read(&path) {
+        let script = match fs::read(path) {
             Ok(bin) => bin,
             Err(why) => panic!("Unable to read {}, reason: {}", path, why),
         };
@@ -53,6 +53,10 @@ impl Cart for LuaScript {
         self.name.clone()
     }
 
+    fn path(&self) -> &String {
+        &self.path
+    }
+
     fn size(&self) -> u32 {
         self.script.len() as u32
     }
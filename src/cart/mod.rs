@@ -11,10 +11,23 @@ use crate::runtime::Runtime;
 use self::{wars_8_binary::Wars8Binary, wasm_binary::WasmBinary};
 pub trait Cart: Send + Sync {
     fn name(&self) -> String;
+    /// Path the cart was loaded from, used to load it again on reset
+    fn path(&self) -> &String;
+    #[allow(dead_code)]
     fn size(&self) -> u32;
     fn binary(&self) -> &[u8];
     fn save(&self) -> Result<(), ()>;
     fn create_runtime(&self) -> Box<dyn Runtime>;
+
+    /// RGB colors the cart replaces the display palette with, in palette order
+    fn palette(&self) -> &[(u8, u8, u8)] {
+        &[]
+    }
+
+    /// Frame rate the cart asks for, overriding the one picked by its runtime
+    fn frame_rate(&self) -> Option<f32> {
+        None
+    }
 }
 
 impl dyn Cart {
@@ -25,10 +38,10 @@ impl dyn Cart {
         };
 
         match ext.to_lowercase().as_str() {
-            "lua" => Box::new(LuaScript::new(&path)),
-            "p8" => Box::new(P8Script::new(&path)),
-            "rs8" => Box::new(Wars8Binary::new(&path)),
-            "wasm" => Box::new(WasmBinary::new(&path)),
+            "lua" => Box::new(LuaScript::new(path)),
+            "p8" => Box::new(P8Script::new(path)),
+            "rs8" => Box::new(Wars8Binary::new(path)),
+            "wasm" => Box::new(WasmBinary::new(path)),
             _ => panic!("Unknown format: {}", ext),
         }
     }
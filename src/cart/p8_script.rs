@@ -9,14 +9,11 @@ pub struct P8Script {
     path: String,
     name: String,
     script: Vec<u8>,
-    sprites: Vec<[[ColorPalette; 8]; 8]>,
-    spritesheet: [i32; 128 * 128],
-    map: [i32; 128 * 64],
 }
 
 impl P8Script {
     pub fn new(path: &String) -> Self {
-        let metadata = match fs::metadata(&path) {
+        let metadata = match fs::metadata(path) {
             Ok(md) => md,
             Err(why) => panic!("Unable to open file {}, reason: {}", path, why),
         };
@@ -40,9 +37,9 @@ impl P8Script {
             None => path.clone(),
         };
 
-        let data = match fs::read_to_string(&path) {
+        let data = match fs::read_to_string(path) {
             Ok(bs) => bs,
-            Err(why) => panic!("Unable to read {}, reason: {}", &path, why),
+            Err(why) => panic!("Unable to read {}, reason: {}", path, why),
         };
 
         let lines = data.split('\n').collect::<Vec<&str>>();
@@ -53,67 +50,76 @@ impl P8Script {
         let mut gfx_start = -1;
         let mut gfx_end = lines.len() as i32;
 
-        let mut gff_start = -1;
-        let mut gff_end = lines.len() as i32;
-
         let mut map_start = -1;
         let mut map_end = lines.len() as i32;
 
+        let mut sfx_start = -1;
+        let mut sfx_end = lines.len() as i32;
+
+        let mut music_start = -1;
+        let mut music_end = lines.len() as i32;
+
         let p8_cart_sec_regex = Regex::new(r"__[a-zA-Z]*__$").unwrap();
 
-        for x in 0..lines.len() {
-            if lua_start != -1 && p8_cart_sec_regex.is_match(lines[x]) {
+        for (x, line) in lines.iter().enumerate() {
+            if lua_start != -1 && p8_cart_sec_regex.is_match(line) {
                 lua_end = x as i32;
                 break;
             }
 
-            if lines[x] == "__lua__" {
+            if *line == "__lua__" {
                 lua_start = x as i32 + 1;
             }
         }
 
-        for x in 0..lines.len() {
-            if gfx_start != -1 && p8_cart_sec_regex.is_match(lines[x]) {
+        for (x, line) in lines.iter().enumerate() {
+            if gfx_start != -1 && p8_cart_sec_regex.is_match(line) {
                 gfx_end = x as i32;
                 break;
             }
 
-            if lines[x] == "__gfx__" {
+            if *line == "__gfx__" {
                 gfx_start = x as i32 + 1;
             }
         }
 
-        for x in 0..lines.len() {
-            if map_start != -1 && p8_cart_sec_regex.is_match(lines[x]) {
+        for (x, line) in lines.iter().enumerate() {
+            if map_start != -1 && p8_cart_sec_regex.is_match(line) {
                 map_end = x as i32;
                 break;
             }
 
-            if lines[x] == "__map__" {
+            if *line == "__map__" {
                 map_start = x as i32 + 1;
             }
         }
 
-        for x in 0..lines.len() {
-            if gff_start != -1 && p8_cart_sec_regex.is_match(lines[x]) {
-                gff_end = x as i32;
+        for (x, line) in lines.iter().enumerate() {
+            if sfx_start != -1 && p8_cart_sec_regex.is_match(line) {
+                sfx_end = x as i32;
                 break;
             }
 
-            if lines[x] == "__gff__" {
-                gff_start = x as i32 + 1;
+            if *line == "__sfx__" {
+                sfx_start = x as i32 + 1;
             }
         }
 
-        let mut script: Vec<u8> = Vec::new();
-        for x in lua_start..lua_end {
-            let mut line = lines[x as usize].to_owned();
-            let mut line_clone = line.clone();
+        for (x, line) in lines.iter().enumerate() {
+            if music_start != -1 && p8_cart_sec_regex.is_match(line) {
+                music_end = x as i32;
+                break;
+            }
 
-            for ch in line.as_bytes() {
-                script.push(*ch);
+            if *line == "__music__" {
+                music_start = x as i32 + 1;
             }
-            script.push('\n' as u8);
+        }
+
+        let mut script: Vec<u8> = Vec::new();
+        for x in lua_start..lua_end {
+            script.extend_from_slice(lines[x as usize].as_bytes());
+            script.push(b'\n');
         }
 
         let mut mem_lock = MEM.lock().unwrap();
@@ -122,11 +128,10 @@ impl P8Script {
         let mut sprites: Vec<[[ColorPalette; 8]; 8]> = Vec::new();
         if gfx_start != -1 {
             for x in 0..(gfx_end - gfx_start) {
-                let mut ctr = 0;
-                for ch in lines[(gfx_start + x) as usize].as_bytes() {
-                    spritesheet[((x * 128) + ctr) as usize] =
+                let line = lines[(gfx_start + x) as usize].as_bytes();
+                for (ctr, ch) in line.iter().enumerate() {
+                    spritesheet[(x * 128) as usize + ctr] =
                         (*ch as char).to_digit(16).unwrap() as i32;
-                    ctr += 1;
                 }
             }
 
@@ -144,19 +149,65 @@ impl P8Script {
             }
         }
 
-        for x in 0..sprites.len() {
-            set_sprite(Some(&mut mem_lock), x as i32, sprites[x]);
+        for (x, sprite) in sprites.iter().enumerate() {
+            set_sprite(Some(&mut mem_lock), x as i32, *sprite);
         }
 
-        let mut map = [0; (128 * 64)];
         if map_start != -1 {
             for idx in 0..(map_end - map_start) {
-                let mut ctr = 0;
-                for ch in lines[(map_start + idx) as usize].as_bytes() {
+                let line = lines[(map_start + idx) as usize].as_bytes();
+                for (ctr, ch) in line.iter().enumerate() {
                     let val = (*ch as char).to_digit(16).unwrap() as u8;
-                    map[((idx * 128) + ctr) as usize] = val as i32;
-                    set_map(Some(&mut mem_lock), ctr, idx, val);
-                    ctr += 1;
+                    set_map(Some(&mut mem_lock), ctr as i32, idx, val);
+                }
+            }
+        }
+
+        if sfx_start != -1 {
+            for idx in 0..(sfx_end - sfx_start).min(64) {
+                let line = lines[(sfx_start + idx) as usize].as_bytes();
+                if line.len() < 168 {
+                    continue;
+                }
+
+                let hex = |pos: usize| (line[pos] as char).to_digit(16).unwrap_or(0) as u8;
+                let base = 0x3200 + (idx as usize * 68);
+
+                // Header is editor mode, speed, loop start, loop end
+                for byte in 0..4 {
+                    mem_lock[base + 64 + byte] = (hex(byte * 2) << 4) | hex((byte * 2) + 1);
+                }
+
+                // Notes are pitch (2), waveform (1), volume (1), effect (1)
+                for note in 0..32 {
+                    let pos = 8 + (note * 5);
+                    let pitch = (hex(pos) << 4) | hex(pos + 1);
+                    let waveform = hex(pos + 2);
+                    let volume = hex(pos + 3) & 0b111;
+                    let effect = hex(pos + 4) & 0b111;
+                    let custom = (waveform >> 3) & 0b1;
+
+                    mem_lock[base + (note * 2)] = (pitch & 0b11_1111) | ((waveform & 0b11) << 6);
+                    mem_lock[base + (note * 2) + 1] =
+                        ((waveform >> 2) & 0b1) | (volume << 1) | (effect << 4) | (custom << 7);
+                }
+            }
+        }
+
+        if music_start != -1 {
+            for idx in 0..(music_end - music_start).min(64) {
+                let line = lines[(music_start + idx) as usize].as_bytes();
+                if line.len() < 11 {
+                    continue;
+                }
+
+                let hex = |pos: usize| (line[pos] as char).to_digit(16).unwrap_or(0) as u8;
+                let flags = (hex(0) << 4) | hex(1);
+                for channel in 0..4 {
+                    let pos = 3 + (channel * 2);
+                    let sfx = ((hex(pos) << 4) | hex(pos + 1)) & 0b111_1111;
+                    mem_lock[0x3100 + (idx as usize * 4) + channel] =
+                        sfx | (((flags >> channel) & 0b1) << 7);
                 }
             }
         }
@@ -165,9 +216,6 @@ impl P8Script {
             path: path.clone(),
             name,
             script,
-            sprites,
-            spritesheet,
-            map,
         }
     }
 }
@@ -177,6 +225,10 @@ impl Cart for P8Script {
         self.name.clone()
     }
 
+    fn path(&self) -> &String {
+        &self.path
+    }
+
     fn size(&self) -> u32 {
         self.script.len() as u32
     }
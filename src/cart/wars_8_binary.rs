@@ -1,12 +1,13 @@
 use crate::palette::ColorPalette;
+use crate::MEM;
 use crate::{
     cart::Cart,
     runtime::{wasm_runtime::WasmRuntime, Runtime},
 };
-use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
 use std::fs;
 use std::io::Cursor;
-use std::io::{Read, Write};
+use std::io::Read;
 
 pub struct Wars8Binary {
     path: String,
@@ -14,13 +15,17 @@ pub struct Wars8Binary {
     binary: Vec<u8>,
     sprites: Vec<[[ColorPalette; 8]; 8]>,
     map: Vec<u8>,
+    palette: Vec<(u8, u8, u8)>,
+    font: Vec<u8>,
+    /// Frames per second, 0 to let the runtime decide
+    frame_rate: u32,
 }
 
 impl Wars8Binary {
     pub const MAGIC: u32 = 0x28061969;
 
     pub fn new(path: &String) -> Wars8Binary {
-        let metadata = match fs::metadata(&path) {
+        let metadata = match fs::metadata(path) {
             Ok(md) => md,
             Err(why) => panic!("Unable to open file {}, reason: {}", path, why),
         };
@@ -44,9 +49,9 @@ impl Wars8Binary {
             None => path.clone(),
         };
 
-        let mut reader = Cursor::new(match fs::read(&path) {
+        let mut reader = Cursor::new(match fs::read(path) {
             Ok(bs) => bs,
-            Err(why) => panic!("Unable to read {}, reason: {}", &path, why),
+            Err(why) => panic!("Unable to read {}, reason: {}", path, why),
         });
 
         fn _corrupt(path: &String, reason: &str) -> ! {
@@ -69,7 +74,7 @@ impl Wars8Binary {
 
         let mut binary = vec![0; binary_size as usize];
         if let Err(why) = reader.read(binary.as_mut_slice()) {
-            _corrupt(path, format!("Unable to read binary: {}", &why).as_str());
+            _corrupt(path, format!("Unable to read binary: {}", why).as_str());
         }
 
         let sprite_count = match reader.read_u32::<LittleEndian>() {
@@ -85,7 +90,7 @@ impl Wars8Binary {
         let mut sprites: Vec<[[ColorPalette; 8]; 8]> = Vec::new();
         for sprite in 0..sprite_count {
             let mut sprite_buffer = [0u8; 8 * 8];
-            if let Err(_) = reader.read(&mut sprite_buffer) {
+            if reader.read(&mut sprite_buffer).is_err() {
                 _corrupt(path, format!("Unable to read sprite {}", sprite).as_str());
             }
 
@@ -97,22 +102,74 @@ impl Wars8Binary {
             }
         }
 
-        let map_count = match reader.read_u32::<LittleEndian>() {
-            Ok(mc) => mc,
-            Err(_) => 0,
-        };
+        let map_count = reader.read_u32::<LittleEndian>().unwrap_or_default();
 
         let mut map = vec![0; map_count as usize];
-        if let Err(_) = reader.read(map.as_mut_slice()) {
+        if reader.read(map.as_mut_slice()).is_err() {
             _corrupt(path, "Unable to read map");
         }
 
+        let palette_count = match reader.read_u32::<LittleEndian>() {
+            Ok(pc) => {
+                if pc > 32 {
+                    _corrupt(path, format!("Palette count {} over limit", pc).as_str());
+                }
+                pc
+            }
+            Err(_) => 0,
+        };
+
+        let mut palette: Vec<(u8, u8, u8)> = Vec::new();
+        for color in 0..palette_count {
+            let mut rgb = [0u8; 3];
+            if reader.read_exact(&mut rgb).is_err() {
+                _corrupt(
+                    path,
+                    format!("Unable to read palette color {}", color).as_str(),
+                );
+            }
+            palette.push((rgb[0], rgb[1], rgb[2]));
+        }
+
+        // Custom font data copied to 0x5600
+        let font_size = match reader.read_u32::<LittleEndian>() {
+            Ok(fs) => {
+                if fs > 0x800 {
+                    _corrupt(path, format!("Font size {:#X} over limit", fs).as_str());
+                }
+                fs
+            }
+            Err(_) => 0,
+        };
+
+        let mut font = vec![0; font_size as usize];
+        if reader.read_exact(font.as_mut_slice()).is_err() {
+            _corrupt(path, "Unable to read font");
+        }
+
+        if !font.is_empty() {
+            MEM.lock().unwrap()[0x5600..(0x5600 + font.len())].copy_from_slice(&font);
+        }
+
+        let frame_rate = match reader.read_u32::<LittleEndian>() {
+            Ok(fr) => {
+                if fr > 240 {
+                    _corrupt(path, format!("Frame rate {} over limit", fr).as_str());
+                }
+                fr
+            }
+            Err(_) => 0,
+        };
+
         Wars8Binary {
             path: path.clone(),
             name,
             binary,
             sprites,
             map,
+            palette,
+            font,
+            frame_rate,
         }
     }
 }
@@ -122,6 +179,10 @@ impl Cart for Wars8Binary {
         self.name.clone()
     }
 
+    fn path(&self) -> &String {
+        &self.path
+    }
+
     fn size(&self) -> u32 {
         self.binary().len() as u32
     }
@@ -140,32 +201,45 @@ impl Cart for Wars8Binary {
 
         let metadata = match fs::metadata(&self.path) {
             Ok(md) => md,
-            Err(why) => panic!("Unable to open file {}, reason: {}", &self.path, why),
+            Err(why) => panic!("Unable to open file {}, reason: {}", self.path, why),
         };
 
         if metadata.is_dir() {
-            panic!("{} is a directory!", &self.path);
+            panic!("{} is a directory!", self.path);
         }
 
         let mut out_buf: Vec<u8> = Vec::new();
-        out_buf.write_u32::<BigEndian>(Wars8Binary::MAGIC);
+        out_buf.extend_from_slice(&Wars8Binary::MAGIC.to_be_bytes());
 
-        out_buf.write_u32::<LittleEndian>(self.binary.len() as u32);
-        out_buf.write(&self.binary);
+        out_buf.extend_from_slice(&(self.binary.len() as u32).to_le_bytes());
+        out_buf.extend_from_slice(&self.binary);
 
-        out_buf.write_u32::<LittleEndian>(self.sprites.len() as u32);
+        out_buf.extend_from_slice(&(self.sprites.len() as u32).to_le_bytes());
         for sprite in &self.sprites {
-            for row in 0..8 {
-                for col in 0..8 {
-                    out_buf.push(i32::from(sprite[row][col]) as u8);
+            for row in sprite {
+                for col in row {
+                    out_buf.push(i32::from(*col) as u8);
                 }
             }
         }
 
-        out_buf.write_u32::<LittleEndian>(self.map.len() as u32);
-        out_buf.write(&self.map);
+        out_buf.extend_from_slice(&(self.map.len() as u32).to_le_bytes());
+        out_buf.extend_from_slice(&self.map);
 
-        fs::write(&self.path, out_buf);
+        out_buf.extend_from_slice(&(self.palette.len() as u32).to_le_bytes());
+        for (r, g, b) in &self.palette {
+            out_buf.extend_from_slice(&[*r, *g, *b]);
+        }
+
+        out_buf.extend_from_slice(&(self.font.len() as u32).to_le_bytes());
+        out_buf.extend_from_slice(&self.font);
+
+        out_buf.extend_from_slice(&self.frame_rate.to_le_bytes());
+
+        if let Err(why) = fs::write(&self.path, out_buf) {
+            println!("Unable to save {}, reason: {}", self.path, why);
+            return Err(());
+        }
 
         Ok(())
     }
@@ -173,4 +247,15 @@ impl Cart for Wars8Binary {
     fn create_runtime(&self) -> Box<dyn Runtime> {
         Box::new(WasmRuntime::new(self.binary()))
     }
+
+    fn palette(&self) -> &[(u8, u8, u8)] {
+        &self.palette
+    }
+
+    fn frame_rate(&self) -> Option<f32> {
+        match self.frame_rate {
+            0 => None,
+            fr => Some(fr as f32),
+        }
+    }
 }
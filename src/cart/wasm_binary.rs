@@ -5,13 +5,14 @@ use crate::{
 use std::fs;
 
 pub struct WasmBinary {
+    path: String,
     name: String,
     binary: Vec<u8>,
 }
 
 impl WasmBinary {
     pub fn new(path: &String) -> Self {
-        let metadata = match fs::metadata(&path) {
+        let metadata = match fs::metadata(path) {
             Ok(md) => md,
             Err(why) => panic!("Unable to open file {}, reason: {}", path, why),
         };
@@ -35,12 +36,16 @@ impl WasmBinary {
             None => path.clone(),
         };
 
-        let binary = match fs::read(&path) {
+        let binary = match fs::read(path) {
             Ok(bin) => bin,
             Err(why) => panic!("Unable to read {}, reason: {}", path, why),
         };
 
-        WasmBinary { name, binary }
+        WasmBinary {
+            path: path.clone(),
+            name,
+            binary,
+        }
     }
 }
 
@@ -49,6 +54,10 @@ impl Cart for WasmBinary {
         self.name.clone()
     }
 
+    fn path(&self) -> &String {
+        &self.path
+    }
+
     fn size(&self) -> u32 {
         self.binary().len() as u32
     }
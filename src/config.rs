@@ -17,12 +17,197 @@ pub struct PlayerKeyBindings {
     pub x: i32,
 }
 
+impl PlayerKeyBindings {
+    /// Returns the scancode bound to PICO-8 button `button`
+    pub fn button(&self, button: i32) -> Option<i32> {
+        match button {
+            0 => Some(self.left),
+            1 => Some(self.right),
+            2 => Some(self.up),
+            3 => Some(self.down),
+            4 => Some(self.o),
+            5 => Some(self.x),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct KeyBindings {
-    pub player1: PlayerKeyBindings,
-    pub player2: PlayerKeyBindings,
+    /// Bindings for up to 8 players, players without an entry can only use a controller
+    #[serde(default = "KeyBindings::default_players")]
+    pub players: Vec<PlayerKeyBindings>,
+    /// Bindings from configs written before `players`, moved into it when loading
+    #[serde(default, skip_serializing)]
+    player1: Option<PlayerKeyBindings>,
+    #[serde(default, skip_serializing)]
+    player2: Option<PlayerKeyBindings>,
     pub quit: i32,
     pub pause: i32,
+    /// Save state hotkeys, only WASM carts support save states
+    #[serde(default = "KeyBindings::default_save_state")]
+    pub save_state: i32,
+    #[serde(default = "KeyBindings::default_load_state")]
+    pub load_state: i32,
+    #[serde(default = "KeyBindings::default_state_slot")]
+    pub state_slot: i32,
+    /// Steps back through the rewind buffer while held
+    #[serde(default = "KeyBindings::default_rewind")]
+    pub rewind: i32,
+}
+
+impl KeyBindings {
+    fn default_save_state() -> i32 {
+        Scancode::F5 as i32
+    }
+
+    fn default_load_state() -> i32 {
+        Scancode::F7 as i32
+    }
+
+    fn default_state_slot() -> i32 {
+        Scancode::F6 as i32
+    }
+
+    fn default_rewind() -> i32 {
+        Scancode::F2 as i32
+    }
+
+    fn default_players() -> Vec<PlayerKeyBindings> {
+        vec![
+            PlayerKeyBindings {
+                left: Scancode::Left as i32,
+                right: Scancode::Right as i32,
+                up: Scancode::Up as i32,
+                down: Scancode::Down as i32,
+                o: Scancode::Z as i32,
+                x: Scancode::X as i32,
+            },
+            PlayerKeyBindings {
+                left: Scancode::S as i32,
+                right: Scancode::F as i32,
+                up: Scancode::E as i32,
+                down: Scancode::D as i32,
+                o: Scancode::Tab as i32,
+                x: Scancode::Q as i32,
+            },
+        ]
+    }
+
+    /// Moves `player1`/`player2` from older configs into `players`
+    fn migrate(&mut self) {
+        let legacy = vec![self.player1.take(), self.player2.take()];
+        for (idx, bindings) in legacy.into_iter().enumerate() {
+            if let Some(bindings) = bindings {
+                match self.players.get_mut(idx) {
+                    Some(player) => *player = bindings,
+                    None => self.players.push(bindings),
+                }
+            }
+        }
+    }
+}
+
+/// SDL game controller button names (such as `a` or `dpleft`) for each PICO-8 button
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct ControllerButtons {
+    pub left: String,
+    pub right: String,
+    pub up: String,
+    pub down: String,
+    pub o: String,
+    pub x: String,
+}
+
+impl Default for ControllerButtons {
+    fn default() -> Self {
+        ControllerButtons {
+            left: "dpleft".to_string(),
+            right: "dpright".to_string(),
+            up: "dpup".to_string(),
+            down: "dpdown".to_string(),
+            o: "a".to_string(),
+            x: "b".to_string(),
+        }
+    }
+}
+
+/// Controller settings of a single player
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ControllerPlayer {
+    /// Name of the controller to assign to this player, as printed when it is plugged in
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Buttons used instead of the shared ones for this player
+    #[serde(default)]
+    pub buttons: Option<ControllerButtons>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ControllerSettings {
+    /// Left stick movement (0 to 32767) ignored before it acts as the d-pad
+    #[serde(default = "ControllerSettings::default_deadzone")]
+    pub deadzone: i32,
+    #[serde(default)]
+    pub buttons: ControllerButtons,
+    /// Extra mappings in the SDL_GameControllerDB format for controllers SDL doesn't know
+    #[serde(default)]
+    pub mappings: Vec<String>,
+    /// Controller assignment and buttons of each player, in player order
+    #[serde(default)]
+    pub players: Vec<ControllerPlayer>,
+}
+
+impl ControllerSettings {
+    fn default_deadzone() -> i32 {
+        8000
+    }
+
+    /// The buttons of `player`'s controller
+    pub fn player_buttons(&self, player: usize) -> &ControllerButtons {
+        self.players
+            .get(player)
+            .and_then(|player| player.buttons.as_ref())
+            .unwrap_or(&self.buttons)
+    }
+
+    /// The name of the controller `player` is reserved for, if any
+    pub fn player_name(&self, player: usize) -> Option<&str> {
+        self.players
+            .get(player)
+            .and_then(|player| player.name.as_deref())
+    }
+}
+
+impl Default for ControllerSettings {
+    fn default() -> Self {
+        ControllerSettings {
+            deadzone: ControllerSettings::default_deadzone(),
+            buttons: ControllerButtons::default(),
+            mappings: Vec::new(),
+            players: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RewindSettings {
+    pub enabled: bool,
+    /// How far back the buffer reaches
+    pub seconds: f32,
+    /// Frames between captures
+    pub interval: u32,
+}
+
+impl Default for RewindSettings {
+    fn default() -> Self {
+        RewindSettings {
+            enabled: false,
+            seconds: 10.0,
+            interval: 2,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -30,12 +215,19 @@ pub struct VideoSettings {
     pub fullscreen: bool,
     pub borderless: bool,
     pub fps: bool,
+    /// `#RRGGBB` colors overriding the 16 standard and 16 extended colors, in that order
+    #[serde(default)]
+    pub palette: Option<Vec<String>>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Config {
     pub video: VideoSettings,
     pub keys: KeyBindings,
+    #[serde(default)]
+    pub controllers: ControllerSettings,
+    #[serde(default)]
+    pub rewind: RewindSettings,
 }
 
 impl Config {
@@ -45,27 +237,21 @@ impl Config {
                 fullscreen: false,
                 borderless: false,
                 fps: true,
+                palette: None,
             },
             keys: KeyBindings {
-                player1: PlayerKeyBindings {
-                    left: Scancode::Left as i32,
-                    right: Scancode::Right as i32,
-                    up: Scancode::Up as i32,
-                    down: Scancode::Down as i32,
-                    o: Scancode::Z as i32,
-                    x: Scancode::X as i32,
-                },
-                player2: PlayerKeyBindings {
-                    left: Scancode::S as i32,
-                    right: Scancode::F as i32,
-                    up: Scancode::E as i32,
-                    down: Scancode::D as i32,
-                    o: Scancode::Tab as i32,
-                    x: Scancode::Q as i32,
-                },
+                players: KeyBindings::default_players(),
+                player1: None,
+                player2: None,
                 quit: Scancode::Escape as i32,
                 pause: Scancode::Grave as i32,
+                save_state: KeyBindings::default_save_state(),
+                load_state: KeyBindings::default_load_state(),
+                state_slot: KeyBindings::default_state_slot(),
+                rewind: KeyBindings::default_rewind(),
             },
+            controllers: ControllerSettings::default(),
+            rewind: RewindSettings::default(),
         }
     }
 
@@ -79,10 +265,7 @@ impl Config {
                     }
                 }
 
-                match config_dir.to_str() {
-                    Some(path) => Some(path.to_string()),
-                    None => None,
-                }
+                config_dir.to_str().map(|path| path.to_string())
             }
             None => None,
         }
@@ -105,15 +288,13 @@ impl Config {
         } else {
             match fs::read_to_string(&path) {
                 Ok(content) => match serde_json::from_str(&content) {
-                    Ok(cfg) => config = cfg,
-                    Err(_) => {
-                        fs::remove_file(&path).unwrap();
-                        if let Err(why) =
-                            fs::write(&path, serde_json::to_string_pretty(&config).unwrap())
-                        {
-                            panic!("Not able to create config! Reason: {}", why);
-                        }
+                    Ok(cfg) => {
+                        config = cfg;
+                        config.keys.migrate();
                     }
+                    // Keep the file so it can be fixed by hand, it is only overwritten if the
+                    // config is saved from the console
+                    Err(why) => println!("Unable to parse config, using defaults. Reason: {}", why),
                 },
                 Err(why) => panic!("Unable to read config file! Reason: {}", why),
             }
@@ -131,11 +312,27 @@ impl Config {
             }
 
             let path = path.join("config.json");
-            fs::remove_file(&path).unwrap();
-
             if let Err(why) = fs::write(path, serde_json::to_string_pretty(self).unwrap()) {
                 panic!("Not able to create config! Reason: {}", why);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ControllerSettings;
+
+    #[test]
+    fn partial_controller_settings() {
+        let settings: ControllerSettings =
+            serde_json::from_str(r#"{ "players": [{}, { "buttons": { "o": "x" } }] }"#).unwrap();
+        assert_eq!(settings.deadzone, 8000);
+        assert!(settings.mappings.is_empty());
+        assert_eq!(settings.player_name(1), None);
+        assert_eq!(settings.player_buttons(0).o, "a");
+        assert_eq!(settings.player_buttons(1).o, "x");
+        assert_eq!(settings.player_buttons(1).left, "dpleft");
+        assert_eq!(settings.player_buttons(7).x, "b");
+    }
+}
@@ -0,0 +1,312 @@
+use sdl2::controller::{Axis, Button, GameController};
+use sdl2::event::Event;
+use sdl2::GameControllerSubsystem;
+
+use crate::config::{ControllerButtons, ControllerSettings};
+
+/// Number of players game controllers can be assigned to
+pub const PLAYER_COUNT: usize = 8;
+
+/// Connected game controllers, each assigned when plugged in to the player reserved for its name
+/// or else the first player without one
+pub struct Controllers {
+    subsystem: GameControllerSubsystem,
+    players: Vec<Option<GameController>>,
+    /// Buttons pressed on each player's controller since the last `take_pressed`
+    pressed: [u8; PLAYER_COUNT],
+}
+
+impl Controllers {
+    pub fn new(subsystem: GameControllerSubsystem, settings: &ControllerSettings) -> Self {
+        for mapping in &settings.mappings {
+            if let Err(why) = subsystem.add_mapping(mapping) {
+                println!(
+                    "Invalid controller mapping `{}`, ignoring. Reason: {}",
+                    mapping, why
+                );
+            }
+        }
+
+        // SDL sends a device added event for controllers connected at startup too
+        Controllers {
+            subsystem,
+            players: (0..PLAYER_COUNT).map(|_| None).collect(),
+            pressed: [0; PLAYER_COUNT],
+        }
+    }
+
+    /// Handles controller hotplug and button press events
+    pub fn handle_event(&mut self, event: &Event, settings: &ControllerSettings) {
+        match *event {
+            Event::ControllerDeviceAdded { which, .. } => {
+                let controller = match self.subsystem.open(which) {
+                    Ok(controller) => controller,
+                    Err(why) => {
+                        println!("Unable to open controller {}. Reason: {}", which, why);
+                        return;
+                    }
+                };
+
+                let instance_id = controller.instance_id();
+                let connected = self.players.iter().flatten();
+                if connected
+                    .map(|c| c.instance_id())
+                    .any(|id| id == instance_id)
+                {
+                    return;
+                }
+
+                // Prefer the player reserved for this controller, then players without a reservation
+                let name = controller.name();
+                let free: Vec<usize> = (0..PLAYER_COUNT)
+                    .filter(|idx| self.players[*idx].is_none())
+                    .collect();
+                let slot = free
+                    .iter()
+                    .find(|idx| settings.player_name(**idx) == Some(name.as_str()))
+                    .or_else(|| {
+                        free.iter()
+                            .find(|idx| settings.player_name(**idx).is_none())
+                    })
+                    .or_else(|| free.first())
+                    .copied();
+
+                if let Some(idx) = slot {
+                    println!(
+                        "Controller `{}` assigned to player {}",
+                        controller.name(),
+                        idx + 1
+                    );
+                    self.players[idx] = Some(controller);
+                }
+            }
+            Event::ControllerDeviceRemoved { which, .. } => {
+                for (idx, slot) in self.players.iter_mut().enumerate() {
+                    if slot.as_ref().map(|c| c.instance_id()) == Some(which) {
+                        println!("Controller removed from player {}", idx + 1);
+                        *slot = None;
+                    }
+                }
+            }
+            Event::ControllerButtonDown { which, button, .. } => {
+                let player = self
+                    .players
+                    .iter()
+                    .position(|slot| slot.as_ref().map(|c| c.instance_id()) == Some(which));
+                if let Some(player) = player {
+                    let buttons = settings.player_buttons(player);
+                    self.pressed[player] |= Controllers::button_bit(button, buttons);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the PICO-8 buttons pressed on each player's controller since the last call
+    pub fn take_pressed(&mut self) -> [u8; PLAYER_COUNT] {
+        std::mem::replace(&mut self.pressed, [0; PLAYER_COUNT])
+    }
+
+    fn mapped_buttons(buttons: &ControllerButtons) -> [&String; 6] {
+        [
+            &buttons.left,
+            &buttons.right,
+            &buttons.up,
+            &buttons.down,
+            &buttons.o,
+            &buttons.x,
+        ]
+    }
+
+    /// The PICO-8 buttons `button` is mapped to in `mapping` as a bitfield
+    fn button_bit(button: Button, mapping: &ControllerButtons) -> u8 {
+        let mut buttons = 0;
+        for (bit, name) in Controllers::mapped_buttons(mapping).iter().enumerate() {
+            if Button::from_string(name) == Some(button) {
+                buttons |= 1 << bit;
+            }
+        }
+        buttons
+    }
+
+    /// Returns the PICO-8 buttons held on each player's controller as a bitfield
+    pub fn held(&self, settings: &ControllerSettings) -> [u8; PLAYER_COUNT] {
+        let mut held = [0; PLAYER_COUNT];
+        for (idx, slot) in self.players.iter().enumerate() {
+            if let Some(controller) = slot {
+                held[idx] = Controllers::buttons(controller, idx, settings);
+            }
+        }
+        held
+    }
+
+    fn buttons(controller: &GameController, player: usize, settings: &ControllerSettings) -> u8 {
+        let mapping = settings.player_buttons(player);
+        let mut buttons = 0;
+        for (bit, name) in Controllers::mapped_buttons(mapping).iter().enumerate() {
+            if let Some(button) = Button::from_string(name) {
+                if controller.button(button) {
+                    buttons |= 1 << bit;
+                }
+            }
+        }
+
+        // The left stick acts as a d-pad outside the deadzone
+        let deadzone = settings.deadzone.clamp(0, i16::MAX as i32);
+        let stick_x = controller.axis(Axis::LeftX) as i32;
+        let stick_y = controller.axis(Axis::LeftY) as i32;
+        if stick_x < -deadzone {
+            buttons |= 0b1;
+        }
+        if stick_x > deadzone {
+            buttons |= 0b10;
+        }
+        if stick_y < -deadzone {
+            buttons |= 0b100;
+        }
+        if stick_y > deadzone {
+            buttons |= 0b1000;
+        }
+
+        buttons
+    }
+}
+
+// Virtual joysticks need SDL 2.0.14 or later at link time, so these only build with
+// `cargo test --features virtual-joystick-tests`
+#[cfg(all(test, feature = "virtual-joystick-tests"))]
+mod tests {
+    use super::Controllers;
+    use crate::config::{ControllerButtons, ControllerPlayer, ControllerSettings};
+    use sdl2::sys::{SDL_Joystick, SDL_JoystickFromInstanceID};
+    use sdl2::EventPump;
+    use std::os::raw::c_int;
+
+    // Virtual joysticks were added in SDL 2.0.14, after the sdl2-sys bindings
+    extern "C" {
+        fn SDL_JoystickAttachVirtual(
+            kind: c_int,
+            axes: c_int,
+            buttons: c_int,
+            hats: c_int,
+        ) -> c_int;
+        fn SDL_JoystickDetachVirtual(device_index: c_int) -> c_int;
+        fn SDL_JoystickSetVirtualAxis(
+            joystick: *mut SDL_Joystick,
+            axis: c_int,
+            value: i16,
+        ) -> c_int;
+        fn SDL_JoystickSetVirtualButton(
+            joystick: *mut SDL_Joystick,
+            button: c_int,
+            value: u8,
+        ) -> c_int;
+    }
+
+    const JOYSTICK_TYPE_GAMECONTROLLER: c_int = 1;
+
+    /// SDL names virtual joysticks `Virtual Joystick` and marks their GUID with `v` and the type
+    const MAPPING: &str = "00000000000000000000000000007601,Virtual Joystick,\
+        a:b0,b:b1,x:b2,dpup:b3,dpdown:b4,dpleft:b5,dpright:b6,leftx:a0,lefty:a1,";
+
+    fn attach() -> c_int {
+        unsafe { SDL_JoystickAttachVirtual(JOYSTICK_TYPE_GAMECONTROLLER, 2, 7, 0) }
+    }
+
+    fn joystick(controllers: &Controllers, player: usize) -> *mut SDL_Joystick {
+        let controller = controllers.players[player].as_ref().unwrap();
+        unsafe { SDL_JoystickFromInstanceID(controller.instance_id() as i32) }
+    }
+
+    fn set_button(controllers: &Controllers, player: usize, button: c_int, down: bool) {
+        let joystick = joystick(controllers, player);
+        assert_eq!(
+            unsafe { SDL_JoystickSetVirtualButton(joystick, button, down as u8) },
+            0
+        );
+    }
+
+    fn pump(events: &mut EventPump, controllers: &mut Controllers, settings: &ControllerSettings) {
+        for event in events.poll_iter() {
+            controllers.handle_event(&event, settings);
+        }
+    }
+
+    // SDL can only be initialized once at a time, so everything is tested in one go
+    #[test]
+    fn virtual_controllers() {
+        sdl2::hint::set("SDL_JOYSTICK_ALLOW_BACKGROUND_EVENTS", "1");
+        let sdl = sdl2::init().unwrap();
+        let mut events = sdl.event_pump().unwrap();
+        let settings = ControllerSettings {
+            mappings: vec![MAPPING.to_string()],
+            players: vec![
+                ControllerPlayer {
+                    name: Some("Missing Controller".to_string()),
+                    buttons: None,
+                },
+                ControllerPlayer {
+                    name: None,
+                    buttons: Some(ControllerButtons {
+                        o: "x".to_string(),
+                        ..ControllerButtons::default()
+                    }),
+                },
+                ControllerPlayer {
+                    name: Some("Virtual Joystick".to_string()),
+                    buttons: None,
+                },
+            ],
+            ..ControllerSettings::default()
+        };
+        let mut controllers = Controllers::new(sdl.game_controller().unwrap(), &settings);
+
+        // The reserved player first, then the first player without a reservation
+        attach();
+        pump(&mut events, &mut controllers, &settings);
+        assert!(controllers.players[2].is_some());
+        let second = attach();
+        pump(&mut events, &mut controllers, &settings);
+        let assigned: Vec<bool> = controllers.players.iter().map(Option::is_some).collect();
+        assert_eq!(
+            assigned,
+            [false, true, true, false, false, false, false, false]
+        );
+
+        // Player 1 has x as O, player 2 uses the shared a
+        set_button(&controllers, 1, 0, true);
+        set_button(&controllers, 1, 2, true);
+        set_button(&controllers, 2, 0, true);
+        pump(&mut events, &mut controllers, &settings);
+        let held = controllers.held(&settings);
+        assert_eq!(held[1], 0b1_0000);
+        assert_eq!(held[2], 0b1_0000);
+        assert_eq!(controllers.take_pressed()[1..3], [0b1_0000, 0b1_0000]);
+
+        // Released again within the frame, only the press latch still has it
+        set_button(&controllers, 1, 2, false);
+        pump(&mut events, &mut controllers, &settings);
+        set_button(&controllers, 1, 2, true);
+        pump(&mut events, &mut controllers, &settings);
+        set_button(&controllers, 1, 2, false);
+        pump(&mut events, &mut controllers, &settings);
+        assert_eq!(controllers.held(&settings)[1], 0);
+        assert_eq!(controllers.take_pressed()[1], 0b1_0000);
+        assert_eq!(controllers.take_pressed()[1], 0);
+
+        // The left stick is a d-pad outside the deadzone
+        let stick = joystick(&controllers, 2);
+        unsafe { SDL_JoystickSetVirtualAxis(stick, 0, -(settings.deadzone as i16) + 1) };
+        unsafe { SDL_JoystickSetVirtualAxis(stick, 1, settings.deadzone as i16 + 1) };
+        pump(&mut events, &mut controllers, &settings);
+        assert_eq!(controllers.held(&settings)[2], 0b1_1000);
+        unsafe { SDL_JoystickSetVirtualAxis(stick, 0, -20000) };
+        pump(&mut events, &mut controllers, &settings);
+        assert_eq!(controllers.held(&settings)[2], 0b1_1001);
+
+        unsafe { SDL_JoystickDetachVirtual(second) };
+        pump(&mut events, &mut controllers, &settings);
+        assert!(controllers.players[1].is_none());
+        assert!(controllers.players[2].is_some());
+    }
+}
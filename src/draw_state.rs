@@ -14,6 +14,8 @@ pub fn reset(mutex_guard: Option<&mut MutexGuard<[u8; 0x8000]>>) {
 
     reset_palette(Some(mg), false);
     reset_palette(Some(mg), true);
+    set_line_endpoint(Some(mg), None);
+    set_clip(Some(mg), 0, 0, 128, 128);
 }
 
 pub fn reset_palette(mutex_guard: Option<&mut MutexGuard<[u8; 0x8000]>>, draw: bool) {
@@ -27,12 +29,11 @@ pub fn reset_palette(mutex_guard: Option<&mut MutexGuard<[u8; 0x8000]>>, draw: b
     };
 
     for col in 0..16 {
-        let offset;
-        if draw {
-            offset = (0x5f00 + col) as usize;
+        let offset = if draw {
+            (0x5f00 + col) as usize
         } else {
-            offset = (0x5f10 + col) as usize;
-        }
+            (0x5f10 + col) as usize
+        };
         mg[offset] = col as u8
     }
 }
@@ -107,10 +108,24 @@ pub fn get_screen_palette(
     ColorPalette::from((val & 0b1111) as i32)
 }
 
+/// Returns the raw screen palette entry for `col`, entries 128-143 are extended colors
+pub fn get_screen_palette_entry(mutex_guard: Option<&MutexGuard<[u8; 0x8000]>>, col: u8) -> u8 {
+    let mutex;
+    let mg = match mutex_guard {
+        Some(mg) => mg,
+        None => {
+            mutex = MEM.lock().unwrap();
+            &mutex
+        }
+    };
+
+    mg[0x5f10 + (col & 0b1111) as usize]
+}
+
 pub fn set_screen_palette(
     mutex_guard: Option<&mut MutexGuard<[u8; 0x8000]>>,
     col: ColorPalette,
-    set_col: ColorPalette,
+    set_col: u8,
 ) {
     let mut mutex;
     let mg = match mutex_guard {
@@ -122,10 +137,11 @@ pub fn set_screen_palette(
     };
 
     let idx = (0x5f10 + i32::from(col)) as usize;
-    mg[idx] = i32::from(set_col) as u8;
+    mg[idx] = set_col & 0b1000_1111;
 }
 
-pub fn get_pen_color(mutex_guard: Option<&MutexGuard<[u8; 0x8000]>>) -> ColorPalette {
+/// The pen color, the high nibble is the secondary color of fill patterns
+pub fn get_pen_color(mutex_guard: Option<&MutexGuard<[u8; 0x8000]>>) -> i32 {
     let mutex;
     let mg = match mutex_guard {
         Some(mg) => mg,
@@ -135,10 +151,10 @@ pub fn get_pen_color(mutex_guard: Option<&MutexGuard<[u8; 0x8000]>>) -> ColorPal
         }
     };
 
-    ColorPalette::from((mg[0x5f25] & 0b1111) as i32)
+    mg[0x5f25] as i32
 }
 
-pub fn set_pen_color(mutex_guard: Option<&mut MutexGuard<[u8; 0x8000]>>, col: ColorPalette) {
+pub fn set_pen_color(mutex_guard: Option<&mut MutexGuard<[u8; 0x8000]>>, col: i32) {
     let mut mutex;
     let mg = match mutex_guard {
         Some(mg) => mg,
@@ -148,13 +164,11 @@ pub fn set_pen_color(mutex_guard: Option<&mut MutexGuard<[u8; 0x8000]>>, col: Co
         }
     };
 
-    let idx = 0x5f25;
-    let old_val = mg[idx];
-
-    mg[idx] = (old_val & 0b1111_0000) | i32::from(col) as u8;
+    mg[0x5f25] = col as u8;
 }
 
-pub fn get_print_cursor(mutex_guard: Option<&MutexGuard<[u8; 0x8000]>>) -> (u8, u8) {
+/// The print cursor, stored as signed bytes at 0x5f26/0x5f27
+pub fn get_print_cursor(mutex_guard: Option<&MutexGuard<[u8; 0x8000]>>) -> (i32, i32) {
     let mutex;
     let mg = match mutex_guard {
         Some(mg) => mg,
@@ -164,16 +178,17 @@ pub fn get_print_cursor(mutex_guard: Option<&MutexGuard<[u8; 0x8000]>>) -> (u8,
         }
     };
 
-    let x = mg[0x5f26];
-    let y = mg[0x5f27];
+    let x = mg[0x5f26] as i8 as i32;
+    let y = mg[0x5f27] as i8 as i32;
 
     (x, y)
 }
 
+/// Sets the print cursor, clamped to -128 to 127
 pub fn set_print_cursor(
     mutex_guard: Option<&mut MutexGuard<[u8; 0x8000]>>,
-    x: Option<u8>,
-    y: Option<u8>,
+    x: Option<i32>,
+    y: Option<i32>,
 ) {
     let mut mutex;
     let mg = match mutex_guard {
@@ -185,11 +200,11 @@ pub fn set_print_cursor(
     };
 
     if let Some(x) = x {
-        mg[0x5f26] = x;
+        mg[0x5f26] = x.clamp(-128, 127) as i8 as u8;
     }
 
     if let Some(y) = y {
-        mg[0x5f27] = y;
+        mg[0x5f27] = y.clamp(-128, 127) as i8 as u8;
     }
 }
 
@@ -232,3 +247,140 @@ pub fn set_camera_offset(
         mg[0x5f2b] = (y >> 8) as u8;
     }
 }
+
+pub fn get_line_endpoint(mutex_guard: Option<&MutexGuard<[u8; 0x8000]>>) -> Option<(i32, i32)> {
+    let mutex;
+    let mg = match mutex_guard {
+        Some(mg) => mg,
+        None => {
+            mutex = MEM.lock().unwrap();
+            &mutex
+        }
+    };
+
+    if mg[0x5f35] != 0 {
+        return None;
+    }
+
+    let x = (((mg[0x5f3d] as u16) << 8) | mg[0x5f3c] as u16) as i16;
+    let y = (((mg[0x5f3f] as u16) << 8) | mg[0x5f3e] as u16) as i16;
+    Some((x as i32, y as i32))
+}
+
+pub fn set_line_endpoint(
+    mutex_guard: Option<&mut MutexGuard<[u8; 0x8000]>>,
+    endpoint: Option<(i32, i32)>,
+) {
+    let mut mutex;
+    let mg = match mutex_guard {
+        Some(mg) => mg,
+        None => {
+            mutex = MEM.lock().unwrap();
+            &mut mutex
+        }
+    };
+
+    match endpoint {
+        Some((x, y)) => {
+            mg[0x5f35] = 0;
+            mg[0x5f3c] = x as u8;
+            mg[0x5f3d] = (x >> 8) as u8;
+            mg[0x5f3e] = y as u8;
+            mg[0x5f3f] = (y >> 8) as u8;
+        }
+        None => mg[0x5f35] = 1,
+    }
+}
+
+pub fn get_clip(mutex_guard: Option<&MutexGuard<[u8; 0x8000]>>) -> (i32, i32, i32, i32) {
+    let mutex;
+    let mg = match mutex_guard {
+        Some(mg) => mg,
+        None => {
+            mutex = MEM.lock().unwrap();
+            &mutex
+        }
+    };
+
+    (
+        mg[0x5f20] as i32,
+        mg[0x5f21] as i32,
+        mg[0x5f22] as i32,
+        mg[0x5f23] as i32,
+    )
+}
+
+pub fn set_clip(
+    mutex_guard: Option<&mut MutexGuard<[u8; 0x8000]>>,
+    x0: i32,
+    y0: i32,
+    x1: i32,
+    y1: i32,
+) {
+    let mut mutex;
+    let mg = match mutex_guard {
+        Some(mg) => mg,
+        None => {
+            mutex = MEM.lock().unwrap();
+            &mut mutex
+        }
+    };
+
+    mg[0x5f20] = x0.clamp(0, 128) as u8;
+    mg[0x5f21] = y0.clamp(0, 128) as u8;
+    mg[0x5f22] = x1.clamp(0, 128) as u8;
+    mg[0x5f23] = y1.clamp(0, 128) as u8;
+}
+
+pub fn get_fill_pattern(mutex_guard: Option<&MutexGuard<[u8; 0x8000]>>) -> (u16, bool) {
+    let mutex;
+    let mg = match mutex_guard {
+        Some(mg) => mg,
+        None => {
+            mutex = MEM.lock().unwrap();
+            &mutex
+        }
+    };
+
+    let pattern = ((mg[0x5f32] as u16) << 8) | mg[0x5f31] as u16;
+    let transparent = (mg[0x5f33] & 0b1) != 0;
+    (pattern, transparent)
+}
+
+pub fn set_fill_pattern(
+    mutex_guard: Option<&mut MutexGuard<[u8; 0x8000]>>,
+    pattern: u16,
+    transparent: bool,
+) {
+    let mut mutex;
+    let mg = match mutex_guard {
+        Some(mg) => mg,
+        None => {
+            mutex = MEM.lock().unwrap();
+            &mut mutex
+        }
+    };
+
+    mg[0x5f31] = pattern as u8;
+    mg[0x5f32] = (pattern >> 8) as u8;
+    mg[0x5f33] = (mg[0x5f33] & 0b1111_1110) | transparent as u8;
+}
+
+/// Returns the tline map (width mask, height mask, x offset, y offset) in tiles
+pub fn get_tline_mask(mutex_guard: Option<&MutexGuard<[u8; 0x8000]>>) -> (i32, i32, i32, i32) {
+    let mutex;
+    let mg = match mutex_guard {
+        Some(mg) => mg,
+        None => {
+            mutex = MEM.lock().unwrap();
+            &mutex
+        }
+    };
+
+    (
+        mg[0x5f38] as i32,
+        mg[0x5f39] as i32,
+        mg[0x5f3a] as i32,
+        mg[0x5f3b] as i32,
+    )
+}
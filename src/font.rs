@@ -1,328 +1,2114 @@
-use std::collections::HashMap;
+/// P8SCII font indexed by byte. Each row has its leftmost pixel in bit 7, glyphs below 128 are
+/// 3x5 pixels in a 4 pixel wide cell and glyphs from 128 are 7x5 pixels in an 8 pixel wide cell.
+/// Lower case bytes hold the regular upper case letters and upper case bytes the small caps
+/// letters, as in PICO-8
+pub const FONT: [[u8; 5]; 256] = [
+    [0, 0, 0, 0, 0], // 0x00
+    [0, 0, 0, 0, 0], // 0x01
+    [0, 0, 0, 0, 0], // 0x02
+    [0, 0, 0, 0, 0], // 0x03
+    [0, 0, 0, 0, 0], // 0x04
+    [0, 0, 0, 0, 0], // 0x05
+    [0, 0, 0, 0, 0], // 0x06
+    [0, 0, 0, 0, 0], // 0x07
+    [0, 0, 0, 0, 0], // 0x08
+    [0, 0, 0, 0, 0], // 0x09
+    [0, 0, 0, 0, 0], // 0x0a
+    [0, 0, 0, 0, 0], // 0x0b
+    [0, 0, 0, 0, 0], // 0x0c
+    [0, 0, 0, 0, 0], // 0x0d
+    [0, 0, 0, 0, 0], // 0x0e
+    [0, 0, 0, 0, 0], // 0x0f
+    // 0x10 '▮'
+    [
+        0b1100_0000, // ##
+        0b1100_0000, // ##
+        0b1100_0000, // ##
+        0b1100_0000, // ##
+        0b1100_0000, // ##
+    ],
+    // 0x11 '■'
+    [
+        0b0000_0000,
+        0b1110_0000, // ###
+        0b1110_0000, // ###
+        0b1110_0000, // ###
+        0b0000_0000,
+    ],
+    // 0x12 '□'
+    [
+        0b0000_0000,
+        0b1110_0000, // ###
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+        0b0000_0000,
+    ],
+    // 0x13 '⁙'
+    [
+        0b1010_0000, // # #
+        0b0000_0000,
+        0b0100_0000, //  #
+        0b0000_0000,
+        0b1010_0000, // # #
+    ],
+    // 0x14 '⁘'
+    [
+        0b0100_0000, //  #
+        0b0000_0000,
+        0b1010_0000, // # #
+        0b0000_0000,
+        0b0100_0000, //  #
+    ],
+    // 0x15 '‖'
+    [
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+    ],
+    // 0x16 '◀'
+    [
+        0b0010_0000, //   #
+        0b0110_0000, //  ##
+        0b1110_0000, // ###
+        0b0110_0000, //  ##
+        0b0010_0000, //   #
+    ],
+    // 0x17 '▶'
+    [
+        0b1000_0000, // #
+        0b1100_0000, // ##
+        0b1110_0000, // ###
+        0b1100_0000, // ##
+        0b1000_0000, // #
+    ],
+    // 0x18 '「'
+    [
+        0b1110_0000, // ###
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b0000_0000,
+        0b0000_0000,
+    ],
+    // 0x19 '」'
+    [
+        0b0000_0000,
+        0b0000_0000,
+        0b0010_0000, //   #
+        0b0010_0000, //   #
+        0b1110_0000, // ###
+    ],
+    // 0x1a '¥'
+    [
+        0b1010_0000, // # #
+        0b0100_0000, //  #
+        0b1110_0000, // ###
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+    ],
+    // 0x1b '•'
+    [
+        0b0000_0000,
+        0b0000_0000,
+        0b0100_0000, //  #
+        0b0000_0000,
+        0b0000_0000,
+    ],
+    // 0x1c '、'
+    [
+        0b0000_0000,
+        0b0000_0000,
+        0b0000_0000,
+        0b1000_0000, // #
+        0b0100_0000, //  #
+    ],
+    // 0x1d '。'
+    [
+        0b0000_0000,
+        0b0000_0000,
+        0b0100_0000, //  #
+        0b1010_0000, // # #
+        0b0100_0000, //  #
+    ],
+    // 0x1e '゛'
+    [
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b0000_0000,
+        0b0000_0000,
+        0b0000_0000,
+    ],
+    // 0x1f '゜'
+    [
+        0b0100_0000, //  #
+        0b1010_0000, // # #
+        0b0100_0000, //  #
+        0b0000_0000,
+        0b0000_0000,
+    ],
+    [0, 0, 0, 0, 0], // 0x20 ' '
+    // 0x21 '!'
+    [
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b0000_0000,
+        0b0100_0000, //  #
+    ],
+    // 0x22 '"'
+    [
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b0000_0000,
+        0b0000_0000,
+        0b0000_0000,
+    ],
+    // 0x23 '#'
+    [
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+        0b1010_0000, // # #
+    ],
+    // 0x24 '$'
+    [
+        0b1110_0000, // ###
+        0b1100_0000, // ##
+        0b1110_0000, // ###
+        0b0110_0000, //  ##
+        0b1110_0000, // ###
+    ],
+    // 0x25 '%'
+    [
+        0b1010_0000, // # #
+        0b0010_0000, //   #
+        0b0100_0000, //  #
+        0b1000_0000, // #
+        0b1010_0000, // # #
+    ],
+    // 0x26 '&'
+    [
+        0b1100_0000, // ##
+        0b1100_0000, // ##
+        0b0110_0000, //  ##
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+    ],
+    // 0x27 '''
+    [
+        0b0100_0000, //  #
+        0b1000_0000, // #
+        0b0000_0000,
+        0b0000_0000,
+        0b0000_0000,
+    ],
+    // 0x28 '('
+    [
+        0b0100_0000, //  #
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b0100_0000, //  #
+    ],
+    // 0x29 ')'
+    [
+        0b0100_0000, //  #
+        0b0010_0000, //   #
+        0b0010_0000, //   #
+        0b0010_0000, //   #
+        0b0100_0000, //  #
+    ],
+    // 0x2a '*'
+    [
+        0b1010_0000, // # #
+        0b0100_0000, //  #
+        0b1110_0000, // ###
+        0b0100_0000, //  #
+        0b1010_0000, // # #
+    ],
+    // 0x2b '+'
+    [
+        0b0000_0000,
+        0b0100_0000, //  #
+        0b1110_0000, // ###
+        0b0100_0000, //  #
+        0b0000_0000,
+    ],
+    // 0x2c ','
+    [
+        0b0000_0000,
+        0b0000_0000,
+        0b0000_0000,
+        0b0100_0000, //  #
+        0b1000_0000, // #
+    ],
+    // 0x2d '-'
+    [
+        0b0000_0000,
+        0b0000_0000,
+        0b1110_0000, // ###
+        0b0000_0000,
+        0b0000_0000,
+    ],
+    // 0x2e '.'
+    [
+        0b0000_0000,
+        0b0000_0000,
+        0b0000_0000,
+        0b0000_0000,
+        0b0100_0000, //  #
+    ],
+    // 0x2f '/'
+    [
+        0b0010_0000, //   #
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b1000_0000, // #
+    ],
+    // 0x30 '0'
+    [
+        0b1110_0000, // ###
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+    ],
+    // 0x31 '1'
+    [
+        0b1100_0000, // ##
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b1110_0000, // ###
+    ],
+    // 0x32 '2'
+    [
+        0b1110_0000, // ###
+        0b0010_0000, //   #
+        0b1110_0000, // ###
+        0b1000_0000, // #
+        0b1110_0000, // ###
+    ],
+    // 0x33 '3'
+    [
+        0b1110_0000, // ###
+        0b0010_0000, //   #
+        0b0110_0000, //  ##
+        0b0010_0000, //   #
+        0b1110_0000, // ###
+    ],
+    // 0x34 '4'
+    [
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+        0b0010_0000, //   #
+        0b0010_0000, //   #
+    ],
+    // 0x35 '5'
+    [
+        0b1110_0000, // ###
+        0b1000_0000, // #
+        0b1110_0000, // ###
+        0b0010_0000, //   #
+        0b1110_0000, // ###
+    ],
+    // 0x36 '6'
+    [
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b1110_0000, // ###
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+    ],
+    // 0x37 '7'
+    [
+        0b1110_0000, // ###
+        0b0010_0000, //   #
+        0b0010_0000, //   #
+        0b0010_0000, //   #
+        0b0010_0000, //   #
+    ],
+    // 0x38 '8'
+    [
+        0b1110_0000, // ###
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+    ],
+    // 0x39 '9'
+    [
+        0b1110_0000, // ###
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+        0b0010_0000, //   #
+        0b0010_0000, //   #
+    ],
+    // 0x3a ':'
+    [
+        0b0000_0000,
+        0b0100_0000, //  #
+        0b0000_0000,
+        0b0100_0000, //  #
+        0b0000_0000,
+    ],
+    // 0x3b ';'
+    [
+        0b0000_0000,
+        0b0100_0000, //  #
+        0b0000_0000,
+        0b0100_0000, //  #
+        0b1000_0000, // #
+    ],
+    // 0x3c '<'
+    [
+        0b0010_0000, //   #
+        0b0100_0000, //  #
+        0b1000_0000, // #
+        0b0100_0000, //  #
+        0b0010_0000, //   #
+    ],
+    // 0x3d '='
+    [
+        0b0000_0000,
+        0b1110_0000, // ###
+        0b0000_0000,
+        0b1110_0000, // ###
+        0b0000_0000,
+    ],
+    // 0x3e '>'
+    [
+        0b1000_0000, // #
+        0b0100_0000, //  #
+        0b0010_0000, //   #
+        0b0100_0000, //  #
+        0b1000_0000, // #
+    ],
+    // 0x3f '?'
+    [
+        0b1110_0000, // ###
+        0b0010_0000, //   #
+        0b0110_0000, //  ##
+        0b0000_0000,
+        0b0100_0000, //  #
+    ],
+    // 0x40 '@'
+    [
+        0b0100_0000, //  #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1000_0000, // #
+        0b0110_0000, //  ##
+    ],
+    // 0x41 'A'
+    [
+        0b0000_0000,
+        0b1110_0000, // ###
+        0b1110_0000, // ###
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+    ],
+    // 0x42 'B'
+    [
+        0b0000_0000,
+        0b1110_0000, // ###
+        0b1100_0000, // ##
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+    ],
+    // 0x43 'C'
+    [
+        0b0000_0000,
+        0b0110_0000, //  ##
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b0110_0000, //  ##
+    ],
+    // 0x44 'D'
+    [
+        0b0000_0000,
+        0b1100_0000, // ##
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+    ],
+    // 0x45 'E'
+    [
+        0b0000_0000,
+        0b1110_0000, // ###
+        0b1100_0000, // ##
+        0b1000_0000, // #
+        0b1110_0000, // ###
+    ],
+    // 0x46 'F'
+    [
+        0b0000_0000,
+        0b1110_0000, // ###
+        0b1100_0000, // ##
+        0b1000_0000, // #
+        0b1000_0000, // #
+    ],
+    // 0x47 'G'
+    [
+        0b0000_0000,
+        0b0110_0000, //  ##
+        0b1000_0000, // #
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+    ],
+    // 0x48 'H'
+    [
+        0b0000_0000,
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+    ],
+    // 0x49 'I'
+    [
+        0b0000_0000,
+        0b1110_0000, // ###
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b1110_0000, // ###
+    ],
+    // 0x4a 'J'
+    [
+        0b0000_0000,
+        0b1110_0000, // ###
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b1100_0000, // ##
+    ],
+    // 0x4b 'K'
+    [
+        0b0000_0000,
+        0b1010_0000, // # #
+        0b1100_0000, // ##
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+    ],
+    // 0x4c 'L'
+    [
+        0b0000_0000,
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b1110_0000, // ###
+    ],
+    // 0x4d 'M'
+    [
+        0b0000_0000,
+        0b1110_0000, // ###
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+    ],
+    // 0x4e 'N'
+    [
+        0b0000_0000,
+        0b1100_0000, // ##
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+    ],
+    // 0x4f 'O'
+    [
+        0b0000_0000,
+        0b0110_0000, //  ##
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1100_0000, // ##
+    ],
+    // 0x50 'P'
+    [
+        0b0000_0000,
+        0b1110_0000, // ###
+        0b1110_0000, // ###
+        0b1000_0000, // #
+        0b1000_0000, // #
+    ],
+    // 0x51 'Q'
+    [
+        0b0000_0000,
+        0b0100_0000, //  #
+        0b1010_0000, // # #
+        0b1100_0000, // ##
+        0b0110_0000, //  ##
+    ],
+    // 0x52 'R'
+    [
+        0b0000_0000,
+        0b1110_0000, // ###
+        0b1100_0000, // ##
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+    ],
+    // 0x53 'S'
+    [
+        0b0000_0000,
+        0b0110_0000, //  ##
+        0b1110_0000, // ###
+        0b0010_0000, //   #
+        0b1100_0000, // ##
+    ],
+    // 0x54 'T'
+    [
+        0b0000_0000,
+        0b1110_0000, // ###
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+    ],
+    // 0x55 'U'
+    [
+        0b0000_0000,
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b0110_0000, //  ##
+    ],
+    // 0x56 'V'
+    [
+        0b0000_0000,
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+        0b0100_0000, //  #
+    ],
+    // 0x57 'W'
+    [
+        0b0000_0000,
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+        0b1110_0000, // ###
+    ],
+    // 0x58 'X'
+    [
+        0b0000_0000,
+        0b1010_0000, // # #
+        0b0100_0000, //  #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+    ],
+    // 0x59 'Y'
+    [
+        0b0000_0000,
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+        0b0010_0000, //   #
+        0b1110_0000, // ###
+    ],
+    // 0x5a 'Z'
+    [
+        0b0000_0000,
+        0b1110_0000, // ###
+        0b0100_0000, //  #
+        0b1000_0000, // #
+        0b1110_0000, // ###
+    ],
+    // 0x5b '['
+    [
+        0b1100_0000, // ##
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b1100_0000, // ##
+    ],
+    // 0x5c '\\'
+    [
+        0b1000_0000, // #
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b0010_0000, //   #
+    ],
+    // 0x5d ']'
+    [
+        0b0110_0000, //  ##
+        0b0010_0000, //   #
+        0b0010_0000, //   #
+        0b0010_0000, //   #
+        0b0110_0000, //  ##
+    ],
+    // 0x5e '^'
+    [
+        0b0100_0000, //  #
+        0b1010_0000, // # #
+        0b0000_0000,
+        0b0000_0000,
+        0b0000_0000,
+    ],
+    // 0x5f '_'
+    [
+        0b0000_0000,
+        0b0000_0000,
+        0b0000_0000,
+        0b0000_0000,
+        0b1110_0000, // ###
+    ],
+    // 0x60 '`'
+    [
+        0b0100_0000, //  #
+        0b0010_0000, //   #
+        0b0000_0000,
+        0b0000_0000,
+        0b0000_0000,
+    ],
+    // 0x61 'a'
+    [
+        0b1110_0000, // ###
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+    ],
+    // 0x62 'b'
+    [
+        0b1110_0000, // ###
+        0b1010_0000, // # #
+        0b1100_0000, // ##
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+    ],
+    // 0x63 'c'
+    [
+        0b0110_0000, //  ##
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b0110_0000, //  ##
+    ],
+    // 0x64 'd'
+    [
+        0b1100_0000, // ##
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+    ],
+    // 0x65 'e'
+    [
+        0b1110_0000, // ###
+        0b1000_0000, // #
+        0b1100_0000, // ##
+        0b1000_0000, // #
+        0b1110_0000, // ###
+    ],
+    // 0x66 'f'
+    [
+        0b1110_0000, // ###
+        0b1000_0000, // #
+        0b1100_0000, // ##
+        0b1000_0000, // #
+        0b1000_0000, // #
+    ],
+    // 0x67 'g'
+    [
+        0b0110_0000, //  ##
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+    ],
+    // 0x68 'h'
+    [
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+    ],
+    // 0x69 'i'
+    [
+        0b1110_0000, // ###
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b1110_0000, // ###
+    ],
+    // 0x6a 'j'
+    [
+        0b1110_0000, // ###
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b1100_0000, // ##
+    ],
+    // 0x6b 'k'
+    [
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1100_0000, // ##
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+    ],
+    // 0x6c 'l'
+    [
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b1110_0000, // ###
+    ],
+    // 0x6d 'm'
+    [
+        0b1110_0000, // ###
+        0b1110_0000, // ###
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+    ],
+    // 0x6e 'n'
+    [
+        0b1100_0000, // ##
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+    ],
+    // 0x6f 'o'
+    [
+        0b0110_0000, //  ##
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1100_0000, // ##
+    ],
+    // 0x70 'p'
+    [
+        0b1110_0000, // ###
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+        0b1000_0000, // #
+        0b1000_0000, // #
+    ],
+    // 0x71 'q'
+    [
+        0b0100_0000, //  #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1100_0000, // ##
+        0b0110_0000, //  ##
+    ],
+    // 0x72 'r'
+    [
+        0b1110_0000, // ###
+        0b1010_0000, // # #
+        0b1100_0000, // ##
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+    ],
+    // 0x73 's'
+    [
+        0b0110_0000, //  ##
+        0b1000_0000, // #
+        0b1110_0000, // ###
+        0b0010_0000, //   #
+        0b1100_0000, // ##
+    ],
+    // 0x74 't'
+    [
+        0b1110_0000, // ###
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+    ],
+    // 0x75 'u'
+    [
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b0110_0000, //  ##
+    ],
+    // 0x76 'v'
+    [
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+        0b0100_0000, //  #
+    ],
+    // 0x77 'w'
+    [
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+        0b1110_0000, // ###
+    ],
+    // 0x78 'x'
+    [
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b0100_0000, //  #
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+    ],
+    // 0x79 'y'
+    [
+        0b1010_0000, // # #
+        0b1010_0000, // # #
+        0b1110_0000, // ###
+        0b0010_0000, //   #
+        0b1110_0000, // ###
+    ],
+    // 0x7a 'z'
+    [
+        0b1110_0000, // ###
+        0b0010_0000, //   #
+        0b0100_0000, //  #
+        0b1000_0000, // #
+        0b1110_0000, // ###
+    ],
+    // 0x7b '{'
+    [
+        0b0110_0000, //  ##
+        0b0100_0000, //  #
+        0b1100_0000, // ##
+        0b0100_0000, //  #
+        0b0110_0000, //  ##
+    ],
+    // 0x7c '|'
+    [
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+        0b0100_0000, //  #
+    ],
+    // 0x7d '}'
+    [
+        0b1100_0000, // ##
+        0b0100_0000, //  #
+        0b0110_0000, //  ##
+        0b0100_0000, //  #
+        0b1100_0000, // ##
+    ],
+    // 0x7e '~'
+    [
+        0b0000_0000,
+        0b0010_0000, //   #
+        0b1110_0000, // ###
+        0b1000_0000, // #
+        0b0000_0000,
+    ],
+    // 0x7f '○'
+    [
+        0b0000_0000,
+        0b0100_0000, //  #
+        0b1010_0000, // # #
+        0b0100_0000, //  #
+        0b0000_0000,
+    ],
+    // 0x80 '█'
+    [
+        0b1111_1110, // #######
+        0b1111_1110, // #######
+        0b1111_1110, // #######
+        0b1111_1110, // #######
+        0b1111_1110, // #######
+    ],
+    // 0x81 '▒'
+    [
+        0b1010_1010, // # # # #
+        0b0101_0100, //  # # #
+        0b1010_1010, // # # # #
+        0b0101_0100, //  # # #
+        0b1010_1010, // # # # #
+    ],
+    // 0x82 '🐱'
+    [
+        0b1000_0010, // #     #
+        0b1111_1110, // #######
+        0b1011_1010, // # ### #
+        0b1111_1110, // #######
+        0b0111_1100, //  #####
+    ],
+    // 0x83 '⬇'
+    [
+        0b0111_1100, //  #####
+        0b1100_0110, // ##   ##
+        0b1100_0110, // ##   ##
+        0b1110_1110, // ### ###
+        0b0111_1100, //  #####
+    ],
+    // 0x84 '░'
+    [
+        0b1000_1000, // #   #
+        0b0010_0010, //   #   #
+        0b1000_1000, // #   #
+        0b0010_0010, //   #   #
+        0b1000_1000, // #   #
+    ],
+    // 0x85 '✽'
+    [
+        0b1001_0010, // #  #  #
+        0b0101_0100, //  # # #
+        0b0011_1000, //   ###
+        0b0101_0100, //  # # #
+        0b1001_0010, // #  #  #
+    ],
+    // 0x86 '●'
+    [
+        0b0111_1100, //  #####
+        0b1111_1110, // #######
+        0b1111_1110, // #######
+        0b1111_1110, // #######
+        0b0111_1100, //  #####
+    ],
+    // 0x87 '♥'
+    [
+        0b0110_1100, //  ## ##
+        0b1111_1110, // #######
+        0b0111_1100, //  #####
+        0b0011_1000, //   ###
+        0b0001_0000, //    #
+    ],
+    // 0x88 '☉'
+    [
+        0b0011_1000, //   ###
+        0b0100_0100, //  #   #
+        0b0101_0100, //  # # #
+        0b0100_0100, //  #   #
+        0b0011_1000, //   ###
+    ],
+    // 0x89 '웃'
+    [
+        0b0011_1000, //   ###
+        0b0011_1000, //   ###
+        0b1111_1110, // #######
+        0b0011_1000, //   ###
+        0b0100_0100, //  #   #
+    ],
+    // 0x8a '⌂'
+    [
+        0b0011_1000, //   ###
+        0b0111_1100, //  #####
+        0b1111_1110, // #######
+        0b0100_0100, //  #   #
+        0b0101_0100, //  # # #
+    ],
+    // 0x8b '⬅'
+    [
+        0b0111_1100, //  #####
+        0b1110_0110, // ###  ##
+        0b1100_0110, // ##   ##
+        0b1110_0110, // ###  ##
+        0b0111_1100, //  #####
+    ],
+    // 0x8c '😐'
+    [
+        0b0111_1100, //  #####
+        0b1011_1010, // # ### #
+        0b1111_1110, // #######
+        0b1000_0010, // #     #
+        0b0111_1100, //  #####
+    ],
+    // 0x8d '♪'
+    [
+        0b0001_1100, //    ###
+        0b0001_0000, //    #
+        0b0001_0000, //    #
+        0b0111_0000, //  ###
+        0b0111_0000, //  ###
+    ],
+    // 0x8e '🅾'
+    [
+        0b0111_1100, //  #####
+        0b1100_0110, // ##   ##
+        0b1101_0110, // ## # ##
+        0b1100_0110, // ##   ##
+        0b0111_1100, //  #####
+    ],
+    // 0x8f '◆'
+    [
+        0b0001_0000, //    #
+        0b0011_1000, //   ###
+        0b0111_1100, //  #####
+        0b0011_1000, //   ###
+        0b0001_0000, //    #
+    ],
+    // 0x90 '…'
+    [
+        0b0000_0000,
+        0b0000_0000,
+        0b0000_0000,
+        0b0000_0000,
+        0b1010_1000, // # # #
+    ],
+    // 0x91 '➡'
+    [
+        0b0111_1100, //  #####
+        0b1100_1110, // ##  ###
+        0b1100_0110, // ##   ##
+        0b1100_1110, // ##  ###
+        0b0111_1100, //  #####
+    ],
+    // 0x92 '★'
+    [
+        0b0001_0000, //    #
+        0b0011_1000, //   ###
+        0b1111_1110, // #######
+        0b0111_1100, //  #####
+        0b0100_0100, //  #   #
+    ],
+    // 0x93 '⧗'
+    [
+        0b1111_1110, // #######
+        0b0111_1100, //  #####
+        0b0011_1000, //   ###
+        0b0111_1100, //  #####
+        0b1111_1110, // #######
+    ],
+    // 0x94 '⬆'
+    [
+        0b0111_1100, //  #####
+        0b1110_1110, // ### ###
+        0b1100_0110, // ##   ##
+        0b1100_0110, // ##   ##
+        0b0111_1100, //  #####
+    ],
+    // 0x95 'ˇ'
+    [
+        0b0100_0100, //  #   #
+        0b0010_1000, //   # #
+        0b0001_0000, //    #
+        0b0000_0000,
+        0b0000_0000,
+    ],
+    // 0x96 '∧'
+    [
+        0b0000_0000,
+        0b0001_0000, //    #
+        0b0010_1000, //   # #
+        0b0100_0100, //  #   #
+        0b1000_0010, // #     #
+    ],
+    // 0x97 '❎'
+    [
+        0b0111_1100, //  #####
+        0b1101_0110, // ## # ##
+        0b1110_1110, // ### ###
+        0b1101_0110, // ## # ##
+        0b0111_1100, //  #####
+    ],
+    // 0x98 '▤'
+    [
+        0b1111_1110, // #######
+        0b0000_0000,
+        0b1111_1110, // #######
+        0b0000_0000,
+        0b1111_1110, // #######
+    ],
+    // 0x99 '▥'
+    [
+        0b1010_1010, // # # # #
+        0b1010_1010, // # # # #
+        0b1010_1010, // # # # #
+        0b1010_1010, // # # # #
+        0b1010_1010, // # # # #
+    ],
+    // 0x9a 'あ'
+    [
+        0b0010_0000, //   #
+        0b1111_1100, // ######
+        0b0010_1100, //   # ##
+        0b0111_1010, //  #### #
+        0b1010_1100, // # # ##
+    ],
+    // 0x9b 'い'
+    [
+        0b1000_0000, // #
+        0b1000_0100, // #    #
+        0b1000_0010, // #     #
+        0b1010_0010, // # #   #
+        0b0100_0000, //  #
+    ],
+    // 0x9c 'う'
+    [
+        0b0011_1000, //   ###
+        0b0000_0000,
+        0b0111_1000, //  ####
+        0b0000_0100, //      #
+        0b0011_1000, //   ###
+    ],
+    // 0x9d 'え'
+    [
+        0b0011_1000, //   ###
+        0b0000_0000,
+        0b1111_1100, // ######
+        0b0011_0000, //   ##
+        0b1100_1110, // ##  ###
+    ],
+    // 0x9e 'お'
+    [
+        0b0010_0100, //   #  #
+        0b1111_1100, // ######
+        0b0010_0000, //   #
+        0b0111_0100, //  ### #
+        0b1010_1100, // # # ##
+    ],
+    // 0x9f 'か'
+    [
+        0b0010_0100, //   #  #
+        0b1111_1100, // ######
+        0b0100_1010, //  #  # #
+        0b0100_1000, //  #  #
+        0b1001_1000, // #  ##
+    ],
+    // 0xa0 'き'
+    [
+        0b0010_0000, //   #
+        0b1111_1100, // ######
+        0b0001_0000, //    #
+        0b1111_1110, // #######
+        0b0111_1000, //  ####
+    ],
+    // 0xa1 'く'
+    [
+        0b0000_1000, //     #
+        0b0001_0000, //    #
+        0b0010_0000, //   #
+        0b0001_0000, //    #
+        0b0000_1000, //     #
+    ],
+    // 0xa2 'け'
+    [
+        0b1000_1000, // #   #
+        0b1111_1110, // #######
+        0b1000_1000, // #   #
+        0b1000_1000, // #   #
+        0b1001_0000, // #  #
+    ],
+    // 0xa3 'こ'
+    [
+        0b0111_1100, //  #####
+        0b0000_0000,
+        0b0000_0000,
+        0b1000_0000, // #
+        0b0111_1110, //  ######
+    ],
+    // 0xa4 'さ'
+    [
+        0b0010_0000, //   #
+        0b1111_1100, // ######
+        0b0001_0000, //    #
+        0b0111_1000, //  ####
+        0b1000_0000, // #
+    ],
+    // 0xa5 'し'
+    [
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b1000_0010, // #     #
+        0b0111_1100, //  #####
+    ],
+    // 0xa6 'す'
+    [
+        0b0001_0000, //    #
+        0b1111_1110, // #######
+        0b0011_1000, //   ###
+        0b0001_0000, //    #
+        0b0010_0000, //   #
+    ],
+    // 0xa7 'せ'
+    [
+        0b0100_1000, //  #  #
+        0b1111_1110, // #######
+        0b0100_1000, //  #  #
+        0b0101_0000, //  # #
+        0b0011_1100, //   ####
+    ],
+    // 0xa8 'そ'
+    [
+        0b1111_1100, // ######
+        0b0001_0000, //    #
+        0b1111_1110, // #######
+        0b0010_0000, //   #
+        0b0011_1100, //   ####
+    ],
+    // 0xa9 'た'
+    [
+        0b0100_0000, //  #
+        0b1111_0000, // ####
+        0b0101_1110, //  # ####
+        0b1001_0000, // #  #
+        0b1000_1110, // #   ###
+    ],
+    // 0xaa 'ち'
+    [
+        0b0100_0000, //  #
+        0b1111_1100, // ######
+        0b0100_0000, //  #
+        0b0111_1100, //  #####
+        0b0000_0110, //      ##
+    ],
+    // 0xab 'つ'
+    [
+        0b0000_0000,
+        0b1111_1100, // ######
+        0b0000_0010, //       #
+        0b0000_0100, //      #
+        0b0011_1000, //   ###
+    ],
+    // 0xac 'て'
+    [
+        0b1111_1110, // #######
+        0b0001_0000, //    #
+        0b0010_0000, //   #
+        0b0010_0000, //   #
+        0b0001_1100, //    ###
+    ],
+    // 0xad 'と'
+    [
+        0b0100_0000, //  #
+        0b0100_1100, //  #  ##
+        0b0111_0000, //  ###
+        0b1000_0000, // #
+        0b0111_1100, //  #####
+    ],
+    // 0xae 'な'
+    [
+        0b0100_0100, //  #   #
+        0b1111_0100, // #### #
+        0b0100_1000, //  #  #
+        0b1001_1100, // #  ###
+        0b1010_1110, // # # ###
+    ],
+    // 0xaf 'に'
+    [
+        0b1011_1110, // # #####
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b1010_0000, // # #
+        0b1001_1110, // #  ####
+    ],
+    // 0xb0 'ぬ'
+    [
+        0b0010_1000, //   # #
+        0b0111_1110, //  ######
+        0b1010_1010, // # # # #
+        0b1010_1110, // # # ###
+        0b0100_1100, //  #  ##
+    ],
+    // 0xb1 'ね'
+    [
+        0b0100_0000, //  #
+        0b1111_0100, // #### #
+        0b0110_1010, //  ## # #
+        0b0100_0110, //  #   ##
+        0b1010_1110, // # # ###
+    ],
+    // 0xb2 'の'
+    [
+        0b0011_1000, //   ###
+        0b0101_0100, //  # # #
+        0b1001_0010, // #  #  #
+        0b1010_0010, // # #   #
+        0b0100_0100, //  #   #
+    ],
+    // 0xb3 'は'
+    [
+        0b1001_0000, // #  #
+        0b1111_1110, // #######
+        0b1001_0000, // #  #
+        0b1011_1000, // # ###
+        0b1011_1010, // # ### #
+    ],
+    // 0xb4 'ひ'
+    [
+        0b1100_1000, // ##  #
+        0b0100_1100, //  #  ##
+        0b1000_0100, // #    #
+        0b1000_0010, // #     #
+        0b0111_1100, //  #####
+    ],
+    // 0xb5 'ふ'
+    [
+        0b0011_0000, //   ##
+        0b0000_1000, //     #
+        0b0010_0000, //   #
+        0b0101_0100, //  # # #
+        0b1001_0010, // #  #  #
+    ],
+    // 0xb6 'へ'
+    [
+        0b0000_0000,
+        0b0010_0000, //   #
+        0b0101_0000, //  # #
+        0b1000_1000, // #   #
+        0b0000_0110, //      ##
+    ],
+    // 0xb7 'ほ'
+    [
+        0b1011_1100, // # ####
+        0b1001_0000, // #  #
+        0b1011_1100, // # ####
+        0b1001_0000, // #  #
+        0b1011_1100, // # ####
+    ],
+    // 0xb8 'ま'
+    [
+        0b0001_0000, //    #
+        0b1111_1110, // #######
+        0b0001_0000, //    #
+        0b0111_0000, //  ###
+        0b1010_1110, // # # ###
+    ],
+    // 0xb9 'み'
+    [
+        0b1111_0000, // ####
+        0b0010_0000, //   #
+        0b0100_1010, //  #  # #
+        0b0111_1010, //  #### #
+        0b1000_1100, // #   ##
+    ],
+    // 0xba 'む'
+    [
+        0b0010_0000, //   #
+        0b1111_1000, // #####
+        0b0010_0010, //   #   #
+        0b0110_0010, //  ##   #
+        0b0111_1100, //  #####
+    ],
+    // 0xbb 'め'
+    [
+        0b0010_1000, //   # #
+        0b0111_1100, //  #####
+        0b1010_1010, // # # # #
+        0b1010_0100, // # #  #
+        0b0100_1000, //  #  #
+    ],
+    // 0xbc 'も'
+    [
+        0b0010_0000, //   #
+        0b1111_1000, // #####
+        0b0010_0000, //   #
+        0b1111_1010, // ##### #
+        0b0011_1100, //   ####
+    ],
+    // 0xbd 'や'
+    [
+        0b0100_1000, //  #  #
+        0b1111_1100, // ######
+        0b0100_0100, //  #   #
+        0b0010_0000, //   #
+        0b0010_0000, //   #
+    ],
+    // 0xbe 'ゆ'
+    [
+        0b1001_0000, // #  #
+        0b1111_1110, // #######
+        0b1001_0010, // #  #  #
+        0b1010_0100, // # #  #
+        0b0011_0000, //   ##
+    ],
+    // 0xbf 'よ'
+    [
+        0b0000_1000, //     #
+        0b0000_1110, //     ###
+        0b0000_1000, //     #
+        0b0111_1000, //  ####
+        0b1001_1110, // #  ####
+    ],
+    // 0xc0 'ら'
+    [
+        0b0011_0000, //   ##
+        0b0000_0000,
+        0b1000_0000, // #
+        0b1111_1000, // #####
+        0b0111_1000, //  ####
+    ],
+    // 0xc1 'り'
+    [
+        0b1000_1000, // #   #
+        0b1000_0100, // #    #
+        0b1000_0100, // #    #
+        0b0000_0100, //      #
+        0b0001_1000, //    ##
+    ],
+    // 0xc2 'る'
+    [
+        0b1111_1100, // ######
+        0b0000_1000, //     #
+        0b0011_1100, //   ####
+        0b0000_1010, //     # #
+        0b0010_1100, //   # ##
+    ],
+    // 0xc3 'れ'
+    [
+        0b0100_0000, //  #
+        0b1111_0100, // #### #
+        0b0110_1010, //  ## # #
+        0b0100_0010, //  #    #
+        0b1010_0010, // # #   #
+    ],
+    // 0xc4 'ろ'
+    [
+        0b1111_1100, // ######
+        0b0000_1000, //     #
+        0b0011_1100, //   ####
+        0b0000_0010, //       #
+        0b0011_1100, //   ####
+    ],
+    // 0xc5 'わ'
+    [
+        0b0100_0000, //  #
+        0b1111_0000, // ####
+        0b0110_1100, //  ## ##
+        0b0100_0010, //  #    #
+        0b1010_1100, // # # ##
+    ],
+    // 0xc6 'を'
+    [
+        0b0010_0000, //   #
+        0b1111_1100, // ######
+        0b0101_0000, //  # #
+        0b1111_1000, // #####
+        0b0011_1100, //   ####
+    ],
+    // 0xc7 'ん'
+    [
+        0b0001_0000, //    #
+        0b0010_0000, //   #
+        0b0100_0000, //  #
+        0b0110_0100, //  ##  #
+        0b1001_1000, // #  ##
+    ],
+    // 0xc8 'っ'
+    [
+        0b0000_0000,
+        0b0000_0000,
+        0b0111_1000, //  ####
+        0b0000_0100, //      #
+        0b0011_0000, //   ##
+    ],
+    // 0xc9 'ゃ'
+    [
+        0b0000_0000,
+        0b0100_1000, //  #  #
+        0b1111_1000, // #####
+        0b0101_0000, //  # #
+        0b0010_0000, //   #
+    ],
+    // 0xca 'ゅ'
+    [
+        0b0000_0000,
+        0b1001_0000, // #  #
+        0b1111_1000, // #####
+        0b1010_1000, // # # #
+        0b0010_0000, //   #
+    ],
+    // 0xcb 'ょ'
+    [
+        0b0000_0000,
+        0b0001_0000, //    #
+        0b0001_1000, //    ##
+        0b0111_0000, //  ###
+        0b1001_1000, // #  ##
+    ],
+    // 0xcc 'ア'
+    [
+        0b1111_1110, // #######
+        0b0000_0100, //      #
+        0b0001_1000, //    ##
+        0b0001_0000, //    #
+        0b0010_0000, //   #
+    ],
+    // 0xcd 'イ'
+    [
+        0b0000_0100, //      #
+        0b0000_1000, //     #
+        0b0011_0000, //   ##
+        0b1001_0000, // #  #
+        0b0001_0000, //    #
+    ],
+    // 0xce 'ウ'
+    [
+        0b0001_0000, //    #
+        0b1111_1110, // #######
+        0b1000_0010, // #     #
+        0b0000_0100, //      #
+        0b0001_1000, //    ##
+    ],
+    // 0xcf 'エ'
+    [
+        0b0000_0000,
+        0b1111_1110, // #######
+        0b0001_0000, //    #
+        0b0001_0000, //    #
+        0b1111_1110, // #######
+    ],
+    // 0xd0 'オ'
+    [
+        0b0000_1000, //     #
+        0b1111_1110, // #######
+        0b0001_1000, //    ##
+        0b0010_1000, //   # #
+        0b1100_1000, // ##  #
+    ],
+    // 0xd1 'カ'
+    [
+        0b0010_0000, //   #
+        0b1111_1100, // ######
+        0b0010_0100, //   #  #
+        0b0100_0100, //  #   #
+        0b1000_1100, // #   ##
+    ],
+    // 0xd2 'キ'
+    [
+        0b0010_0000, //   #
+        0b1111_1110, // #######
+        0b0001_0000, //    #
+        0b1111_1110, // #######
+        0b0001_0000, //    #
+    ],
+    // 0xd3 'ク'
+    [
+        0b0010_0000, //   #
+        0b0111_1100, //  #####
+        0b1000_0100, // #    #
+        0b0000_1000, //     #
+        0b0011_0000, //   ##
+    ],
+    // 0xd4 'ケ'
+    [
+        0b0100_0000, //  #
+        0b0111_1110, //  ######
+        0b1000_1000, // #   #
+        0b0000_1000, //     #
+        0b0001_0000, //    #
+    ],
+    // 0xd5 'コ'
+    [
+        0b1111_1110, // #######
+        0b0000_0010, //       #
+        0b0000_0010, //       #
+        0b0000_0010, //       #
+        0b1111_1110, // #######
+    ],
+    // 0xd6 'サ'
+    [
+        0b0100_0100, //  #   #
+        0b1111_1110, // #######
+        0b0100_0100, //  #   #
+        0b0000_0100, //      #
+        0b0001_1000, //    ##
+    ],
+    // 0xd7 'シ'
+    [
+        0b1100_0010, // ##    #
+        0b0000_0100, //      #
+        0b1100_0100, // ##   #
+        0b0000_1000, //     #
+        0b1110_0000, // ###
+    ],
+    // 0xd8 'ス'
+    [
+        0b1111_1100, // ######
+        0b0000_0100, //      #
+        0b0000_1000, //     #
+        0b0011_0100, //   ## #
+        0b1100_0010, // ##    #
+    ],
+    // 0xd9 'セ'
+    [
+        0b0100_0000, //  #
+        0b1111_1110, // #######
+        0b0100_0100, //  #   #
+        0b0100_0000, //  #
+        0b0011_1100, //   ####
+    ],
+    // 0xda 'ソ'
+    [
+        0b1000_0010, // #     #
+        0b0100_0010, //  #    #
+        0b0000_0100, //      #
+        0b0000_1000, //     #
+        0b0011_0000, //   ##
+    ],
+    // 0xdb 'タ'
+    [
+        0b0010_0000, //   #
+        0b0111_1100, //  #####
+        0b1010_0100, // # #  #
+        0b0001_1000, //    ##
+        0b0010_0000, //   #
+    ],
+    // 0xdc 'チ'
+    [
+        0b0011_1100, //   ####
+        0b0001_0000, //    #
+        0b1111_1110, // #######
+        0b0001_0000, //    #
+        0b0010_0000, //   #
+    ],
+    // 0xdd 'ツ'
+    [
+        0b1010_0010, // # #   #
+        0b1010_0010, // # #   #
+        0b0000_0100, //      #
+        0b0000_1000, //     #
+        0b0011_0000, //   ##
+    ],
+    // 0xde 'テ'
+    [
+        0b0111_1100, //  #####
+        0b0000_0000,
+        0b1111_1110, // #######
+        0b0001_0000, //    #
+        0b0010_0000, //   #
+    ],
+    // 0xdf 'ト'
+    [
+        0b0010_0000, //   #
+        0b0010_0000, //   #
+        0b0011_1000, //   ###
+        0b0010_0100, //   #  #
+        0b0010_0000, //   #
+    ],
+    // 0xe0 'ナ'
+    [
+        0b0001_0000, //    #
+        0b1111_1110, // #######
+        0b0001_0000, //    #
+        0b0010_0000, //   #
+        0b0100_0000, //  #
+    ],
+    // 0xe1 'ニ'
+    [
+        0b0000_0000,
+        0b0111_1100, //  #####
+        0b0000_0000,
+        0b0000_0000,
+        0b1111_1110, // #######
+    ],
+    // 0xe2 'ヌ'
+    [
+        0b1111_1100, // ######
+        0b0000_0100, //      #
+        0b0010_1000, //   # #
+        0b0001_0000, //    #
+        0b1100_1000, // ##  #
+    ],
+    // 0xe3 'ネ'
+    [
+        0b0001_0000, //    #
+        0b1111_1100, // ######
+        0b0000_1000, //     #
+        0b0101_0100, //  # # #
+        0b1001_0010, // #  #  #
+    ],
+    // 0xe4 'ノ'
+    [
+        0b0000_0010, //       #
+        0b0000_0100, //      #
+        0b0000_1000, //     #
+        0b0011_0000, //   ##
+        0b1100_0000, // ##
+    ],
+    // 0xe5 'ハ'
+    [
+        0b0010_1000, //   # #
+        0b0010_0100, //   #  #
+        0b0100_0010, //  #    #
+        0b0100_0010, //  #    #
+        0b1000_0000, // #
+    ],
+    // 0xe6 'ヒ'
+    [
+        0b1000_0000, // #
+        0b1000_1100, // #   ##
+        0b1110_0000, // ###
+        0b1000_0000, // #
+        0b0111_1100, //  #####
+    ],
+    // 0xe7 'フ'
+    [
+        0b1111_1110, // #######
+        0b0000_0010, //       #
+        0b0000_0100, //      #
+        0b0000_1000, //     #
+        0b0011_0000, //   ##
+    ],
+    // 0xe8 'ヘ'
+    [
+        0b0000_0000,
+        0b0010_0000, //   #
+        0b0101_0000, //  # #
+        0b1000_1000, // #   #
+        0b0000_0110, //      ##
+    ],
+    // 0xe9 'ホ'
+    [
+        0b0001_0000, //    #
+        0b1111_1110, // #######
+        0b0001_0000, //    #
+        0b1010_1010, // # # # #
+        0b0001_0000, //    #
+    ],
+    // 0xea 'マ'
+    [
+        0b1111_1110, // #######
+        0b0000_0010, //       #
+        0b0010_1100, //   # ##
+        0b0001_0000, //    #
+        0b0000_1000, //     #
+    ],
+    // 0xeb 'ミ'
+    [
+        0b1111_0000, // ####
+        0b0000_1100, //     ##
+        0b1111_0000, // ####
+        0b0000_1100, //     ##
+        0b1111_1100, // ######
+    ],
+    // 0xec 'ム'
+    [
+        0b0001_0000, //    #
+        0b0010_0000, //   #
+        0b0100_0100, //  #   #
+        0b1000_0100, // #    #
+        0b1111_1110, // #######
+    ],
+    // 0xed 'メ'
+    [
+        0b0000_0100, //      #
+        0b1000_1000, // #   #
+        0b0101_0000, //  # #
+        0b0010_1000, //   # #
+        0b1100_0100, // ##   #
+    ],
+    // 0xee 'モ'
+    [
+        0b0111_1100, //  #####
+        0b0001_0000, //    #
+        0b1111_1110, // #######
+        0b0001_0000, //    #
+        0b0001_1110, //    ####
+    ],
+    // 0xef 'ヤ'
+    [
+        0b0100_0000, //  #
+        0b1111_1110, // #######
+        0b0100_0100, //  #   #
+        0b0010_0000, //   #
+        0b0010_0000, //   #
+    ],
+    // 0xf0 'ユ'
+    [
+        0b0111_1100, //  #####
+        0b0000_0100, //      #
+        0b0000_0100, //      #
+        0b0000_0100, //      #
+        0b1111_1110, // #######
+    ],
+    // 0xf1 'ヨ'
+    [
+        0b1111_1100, // ######
+        0b0000_0100, //      #
+        0b1111_1100, // ######
+        0b0000_0100, //      #
+        0b1111_1100, // ######
+    ],
+    // 0xf2 'ラ'
+    [
+        0b0111_1100, //  #####
+        0b0000_0000,
+        0b1111_1110, // #######
+        0b0000_0010, //       #
+        0b0001_1100, //    ###
+    ],
+    // 0xf3 'リ'
+    [
+        0b1000_0100, // #    #
+        0b1000_0100, // #    #
+        0b1000_0100, // #    #
+        0b0000_0100, //      #
+        0b0001_1000, //    ##
+    ],
+    // 0xf4 'ル'
+    [
+        0b0010_1000, //   # #
+        0b0010_1000, //   # #
+        0b0010_1000, //   # #
+        0b0100_1010, //  #  # #
+        0b1000_1100, // #   ##
+    ],
+    // 0xf5 'レ'
+    [
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b1000_0100, // #    #
+        0b1001_1000, // #  ##
+        0b1110_0000, // ###
+    ],
+    // 0xf6 'ロ'
+    [
+        0b1111_1110, // #######
+        0b1000_0010, // #     #
+        0b1000_0010, // #     #
+        0b1000_0010, // #     #
+        0b1111_1110, // #######
+    ],
+    // 0xf7 'ワ'
+    [
+        0b1111_1110, // #######
+        0b1000_0010, // #     #
+        0b0000_0100, //      #
+        0b0000_1000, //     #
+        0b0011_0000, //   ##
+    ],
+    // 0xf8 'ヲ'
+    [
+        0b1111_1110, // #######
+        0b0000_0010, //       #
+        0b1111_1110, // #######
+        0b0000_0100, //      #
+        0b0011_0000, //   ##
+    ],
+    // 0xf9 'ン'
+    [
+        0b1100_0000, // ##
+        0b0000_0000,
+        0b0000_0010, //       #
+        0b0000_0100, //      #
+        0b1111_1000, // #####
+    ],
+    // 0xfa 'ッ'
+    [
+        0b0000_0000,
+        0b1010_0100, // # #  #
+        0b1010_0100, // # #  #
+        0b0000_1000, //     #
+        0b0011_0000, //   ##
+    ],
+    // 0xfb 'ャ'
+    [
+        0b0000_0000,
+        0b0010_0000, //   #
+        0b1111_1100, // ######
+        0b0001_0000, //    #
+        0b0001_0000, //    #
+    ],
+    // 0xfc 'ュ'
+    [
+        0b0000_0000,
+        0b0000_0000,
+        0b0111_1000, //  ####
+        0b0001_0000, //    #
+        0b1111_1100, // ######
+    ],
+    // 0xfd 'ョ'
+    [
+        0b0000_0000,
+        0b0111_1000, //  ####
+        0b0111_1000, //  ####
+        0b0000_1000, //     #
+        0b0111_1000, //  ####
+    ],
+    // 0xfe '◜'
+    [
+        0b0011_1100, //   ####
+        0b0100_0000, //  #
+        0b1000_0000, // #
+        0b1000_0000, // #
+        0b0000_0000,
+    ],
+    // 0xff '◝'
+    [
+        0b0111_1000, //  ####
+        0b0000_0100, //      #
+        0b0000_0010, //       #
+        0b0000_0010, //       #
+        0b0000_0000,
+    ],
+];
 
-lazy_static! {
-    pub static ref FONT: HashMap<char, &'static [[bool; 4]; 6]> =  {
-        let mut m = HashMap::new();
-        m.insert('A', &[
-            [false, true,  false, false], //  #
-            [true,  false, true,  false], // # #
-            [true,  true,  true,  false], // ###
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [false, false, false, false], //
-        ]);
-        m.insert('B', &[
-            [true,  true,  true,  false], // ###
-            [true,  false, true,  false], // # #
-            [true,  true,  false, false], // ##
-            [true,  false, true,  false], // # #
-            [true,  true,  true,  false], // ###
-            [false, false, false, false], //
-        ]);
-        m.insert('C', &[
-            [false, true,  true,  false], //  ##
-            [true,  false, false, false], // #
-            [true,  false, false, false], // #
-            [true,  false, false, false], // #
-            [false, true,  true,  false], //  ##
-            [false, false, false, false], //
-        ]);
-        m.insert('D', &[
-            [true,  true,  false, false], // ##
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [true,  true,  false, false], // ##
-            [false, false, false, false], //
-        ]);
-        m.insert('E', &[
-            [true,  true,  true,  false], // ###
-            [true,  false, false, false], // #
-            [true,  true,  false, false], // ##
-            [true,  false, false, false], // #
-            [true,  true,  true,  false], // ###
-            [false, false, false, false], //
-        ]);
-        m.insert('F', &[
-            [true,  true,  true,  false], // ###
-            [true,  false, false, false], // #
-            [true,  true,  false, false], // ##
-            [true,  false, false, false], // #
-            [true,  false, false, false], // #
-            [false, false, false, false], //
-        ]);
-        m.insert('G', &[
-            [true,  true,  true,  false], // ###
-            [true,  false, false, false], // #
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [true,  true,  true,  false], // ###
-            [false, false, false, false], //
-        ]);
-        m.insert('H', &[
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [true,  true,  true,  false], // ###
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [false, false, false, false], //
-        ]);
-        m.insert('I', &[
-            [true,  true,  true,  false], // ###
-            [false, true,  false, false], //  #
-            [false, true,  false, false], //  #
-            [false, true,  false, false], //  #
-            [true,  true,  true,  false], // ###
-            [false, false, false, false], //
-        ]);
-        m.insert('J', &[
-            [true,  true,  true,  false], // ###
-            [false, true,  false, false], //  #
-            [false, true,  false, false], //  #
-            [false, true,  false, false], //  #
-            [true,  false, false, false], // #
-            [false, false, false, false], //
-        ]);
-        m.insert('K', &[
-            [true,  false, true,  false], // # #
-            [true,  true,  false, false], // ##
-            [true,  true,  false, false], // ##
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [false, false, false, false], //
-        ]);
-        m.insert('L', &[
-            [true,  false, false, false], // #
-            [true,  false, false, false], // #
-            [true,  false, false, false], // #
-            [true,  false, false, false], // #
-            [true,  true,  true,  false], // ###
-            [false, false, false, false], //
-        ]);
-        m.insert('M', &[
-            [true,  false, true,  false], // # #
-            [true,  true,  true,  false], // ###
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [false, false, false, false], //
-        ]);
-        m.insert('N', &[
-            [true,  true,  true,  false], // ###
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [false, false, false, false], //
-        ]);
-        m.insert('O', &[
-            [true,  true,  false, false], // ##
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [false, true,  true,  false], //  ##
-            [false, false, false, false], //
-        ]);
-        m.insert('P', &[
-            [true,  true,  true,  false], // ###
-            [true,  false, true,  false], // # #
-            [true,  true,  true,  false], // ###
-            [true,  false, false, false], // #
-            [true,  false, false, false], // #
-            [false, false, false, false], //
-        ]);
-        m.insert('Q', &[
-            [true,  true,  true,  false], // ###
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [true,  true,  true,  false], // ###
-            [false, false, false, true],  //    #
-            [false, false, false, false], //
-        ]);
-        m.insert('R', &[
-            [true,  true,  true,  false], // ###
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [true,  true,  false, false], // ##
-            [true,  false, true,  false], // # #
-            [false, false, false, false], //
-        ]);
-        m.insert('S', &[
-            [true,  true,  true,  false], // ###
-            [true,  false, false, false], // #
-            [true,  true,  true,  false], // ###
-            [false, false, true,  false], //   #
-            [true,  true,  true,  false], // ###
-            [false, false, false, false], //
-        ]);
-        m.insert('T', &[
-            [true,  true,  true,  false], // ###
-            [false, true,  false, false], //  #
-            [false, true,  false, false], //  #
-            [false, true,  false, false], //  #
-            [false, true,  false, false], //  #
-            [false, false, false, false], //
-        ]);
-        m.insert('U', &[
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [true,  true,  true,  false], // ###
-            [false, false, false, false], //
-        ]);
-        m.insert('V', &[
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [false, true,  false, false], //  #
-            [false, false, false, false], //
-        ]);
-        m.insert('W', &[
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [true,  true,  true,  false], // ###
-            [true,  false, true,  false], // # #
-            [false, false, false, false], //
-        ]);
-        m.insert('X', &[
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [false, true,  false, false], //  #
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [false, false, false, false], //
-        ]);
-        m.insert('Y', &[
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [false, true,  false, false], //  #
-            [false, true,  false, false], //  #
-            [false, true,  false, false], //  #
-            [false, false, false, false], //
-        ]);
-        m.insert('Z', &[
-            [true,  true,  true,  false], // ###
-            [false, false, true,  false], //   #
-            [false, true,  false, false], //  #
-            [true,  false, false, false], // #
-            [true,  true,  true,  false], // ###
-            [false, false, false, false], //
-        ]);
-        m.insert('1', &[
-            [true,  false, false, false], // #
-            [false, true,  false, false], //  #
-            [false, true,  false, false], //  #
-            [false, true,  false, false], //  #
-            [true,  true,  true,  false], // ###
-            [false, false, false, false], //
-        ]);
-        m.insert('2', &[
-            [true,  true,  false, false], // ##
-            [false, false, true,  false], //   #
-            [false, true,  false, false], //  #
-            [true,  false, false, false], // #
-            [false, true,  true,  false], //  ##
-            [false, false, false, false], //
-        ]);
-        m.insert('3', &[
-            [true,  true,  true,  false], // ###
-            [false, false, true,  false], //   #
-            [false, true,  true,  false], // ###
-            [false, false, true,  false], //   #
-            [true,  true,  true,  false], // ###
-            [false, false, false, false], //
-        ]);
-        m.insert('4', &[
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [false, true,  true,  false], //  ##
-            [false, false, true,  false], //   #
-            [false, false, true,  false], //   #
-            [false, false, false, false], //
-        ]);
-        m.insert('5', &[
-            [true,  true,  true,  false], // ###
-            [true, false, false, false], // #
-            [false, true, false, false], //  #
-            [false, false, true, false], //   #
-            [true, true, false, false], // ##
-            [false, false, false, false], //
-        ]);
-        m.insert('6', &[
-            [true,  true,  true,  false], // ###
-            [true,  false, false, false], // #
-            [true,  true,  true,  false], // ###
-            [true,  false, true,  false], // # #
-            [true,  true,  true,  false], // ###
-            [false, false, false, false], //
-        ]);
-        m.insert('7', &[
-            [true,  true,  false, false], // ##
-            [false, false, true,  false], //   #
-            [false, false, true,  false], //   #
-            [false, false, true,  false], //   #
-            [false, false, true,  false], //   #
-            [false, false, false, false], //
-        ]);
-        m.insert('8', &[
-            [true,  true,  true,  false], // ###
-            [true,  false, true,  false], // # #
-            [true,  true,  true,  false], // ###
-            [true,  false, true,  false], // # #
-            [true,  true,  true,  false], // ###
-            [false, false, false, false], //
-        ]);
-        m.insert('9', &[
-            [true,  true,  true,  false], // ###
-            [true,  false, true,  false], // # #
-            [true,  true,  true,  false], // ###
-            [false, false, true,  false], //   #
-            [false, false, true,  false], //   #
-            [false, false, false, false], //
-        ]);
-        m.insert('0', &[
-            [true,  true,  true,  false], // ###
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [true,  false, true,  false], // # #
-            [true,  true,  true,  false], // ###
-            [false, false, false, false], //
-        ]);
-        m.insert('/', &[
-            [false, false, true,  false], //   #
-            [false, true, false,  false], //  #
-            [false, true, false,  false], //  #
-            [false, true, false,  false], //  #
-            [true, false, false,  false], // #
-            [false, false, false, false], //
-        ]);
-        m.insert('\\', &[
-            [true, false, false,  false], // #
-            [false, true, false,  false], //  #
-            [false, true, false,  false], //  #
-            [false, true, false,  false], //  #
-            [false, false, true,  false], //   #
-            [false, false, false, false], //
-        ]);
-        m.insert(',', &[
-            [false, false, false, false], //
-            [false, false, false, false], //
-            [false, false, false, false], //
-            [false, true,  false, false], //  #
-            [true,  false, false, false], // #
-            [false, false, false, false], //
-        ]);
-        m.insert('.', &[
-            [false, false, false, false], //
-            [false, false, false, false], //
-            [false, false, false, false], //
-            [true,  true,  false, false], // ##
-            [true,  true,  false, false], // ##
-            [false, false, false, false], //
-        ]);
-        m
-    };
+/// Unicode characters used for P8SCII glyphs outside of printable ASCII in PICO-8 carts
+const UNICODE: [(char, u8); 145] = [
+    ('▮', 0x10),
+    ('■', 0x11),
+    ('□', 0x12),
+    ('⁙', 0x13),
+    ('⁘', 0x14),
+    ('‖', 0x15),
+    ('◀', 0x16),
+    ('▶', 0x17),
+    ('「', 0x18),
+    ('」', 0x19),
+    ('¥', 0x1a),
+    ('•', 0x1b),
+    ('、', 0x1c),
+    ('。', 0x1d),
+    ('゛', 0x1e),
+    ('゜', 0x1f),
+    ('○', 0x7f),
+    ('█', 0x80),
+    ('▒', 0x81),
+    ('🐱', 0x82),
+    ('⬇', 0x83),
+    ('░', 0x84),
+    ('✽', 0x85),
+    ('●', 0x86),
+    ('♥', 0x87),
+    ('☉', 0x88),
+    ('웃', 0x89),
+    ('⌂', 0x8a),
+    ('⬅', 0x8b),
+    ('😐', 0x8c),
+    ('♪', 0x8d),
+    ('🅾', 0x8e),
+    ('◆', 0x8f),
+    ('…', 0x90),
+    ('➡', 0x91),
+    ('★', 0x92),
+    ('⧗', 0x93),
+    ('⬆', 0x94),
+    ('ˇ', 0x95),
+    ('∧', 0x96),
+    ('❎', 0x97),
+    ('▤', 0x98),
+    ('▥', 0x99),
+    ('あ', 0x9a),
+    ('い', 0x9b),
+    ('う', 0x9c),
+    ('え', 0x9d),
+    ('お', 0x9e),
+    ('か', 0x9f),
+    ('き', 0xa0),
+    ('く', 0xa1),
+    ('け', 0xa2),
+    ('こ', 0xa3),
+    ('さ', 0xa4),
+    ('し', 0xa5),
+    ('す', 0xa6),
+    ('せ', 0xa7),
+    ('そ', 0xa8),
+    ('た', 0xa9),
+    ('ち', 0xaa),
+    ('つ', 0xab),
+    ('て', 0xac),
+    ('と', 0xad),
+    ('な', 0xae),
+    ('に', 0xaf),
+    ('ぬ', 0xb0),
+    ('ね', 0xb1),
+    ('の', 0xb2),
+    ('は', 0xb3),
+    ('ひ', 0xb4),
+    ('ふ', 0xb5),
+    ('へ', 0xb6),
+    ('ほ', 0xb7),
+    ('ま', 0xb8),
+    ('み', 0xb9),
+    ('む', 0xba),
+    ('め', 0xbb),
+    ('も', 0xbc),
+    ('や', 0xbd),
+    ('ゆ', 0xbe),
+    ('よ', 0xbf),
+    ('ら', 0xc0),
+    ('り', 0xc1),
+    ('る', 0xc2),
+    ('れ', 0xc3),
+    ('ろ', 0xc4),
+    ('わ', 0xc5),
+    ('を', 0xc6),
+    ('ん', 0xc7),
+    ('っ', 0xc8),
+    ('ゃ', 0xc9),
+    ('ゅ', 0xca),
+    ('ょ', 0xcb),
+    ('ア', 0xcc),
+    ('イ', 0xcd),
+    ('ウ', 0xce),
+    ('エ', 0xcf),
+    ('オ', 0xd0),
+    ('カ', 0xd1),
+    ('キ', 0xd2),
+    ('ク', 0xd3),
+    ('ケ', 0xd4),
+    ('コ', 0xd5),
+    ('サ', 0xd6),
+    ('シ', 0xd7),
+    ('ス', 0xd8),
+    ('セ', 0xd9),
+    ('ソ', 0xda),
+    ('タ', 0xdb),
+    ('チ', 0xdc),
+    ('ツ', 0xdd),
+    ('テ', 0xde),
+    ('ト', 0xdf),
+    ('ナ', 0xe0),
+    ('ニ', 0xe1),
+    ('ヌ', 0xe2),
+    ('ネ', 0xe3),
+    ('ノ', 0xe4),
+    ('ハ', 0xe5),
+    ('ヒ', 0xe6),
+    ('フ', 0xe7),
+    ('ヘ', 0xe8),
+    ('ホ', 0xe9),
+    ('マ', 0xea),
+    ('ミ', 0xeb),
+    ('ム', 0xec),
+    ('メ', 0xed),
+    ('モ', 0xee),
+    ('ヤ', 0xef),
+    ('ユ', 0xf0),
+    ('ヨ', 0xf1),
+    ('ラ', 0xf2),
+    ('リ', 0xf3),
+    ('ル', 0xf4),
+    ('レ', 0xf5),
+    ('ロ', 0xf6),
+    ('ワ', 0xf7),
+    ('ヲ', 0xf8),
+    ('ン', 0xf9),
+    ('ッ', 0xfa),
+    ('ャ', 0xfb),
+    ('ュ', 0xfc),
+    ('ョ', 0xfd),
+    ('◜', 0xfe),
+    ('◝', 0xff),
+];
+
+pub fn glyph_width(ch: u8) -> i32 {
+    if ch >= 128 {
+        8
+    } else {
+        4
+    }
+}
+
+/// Converts a string from a cart to P8SCII, characters without a glyph are dropped
+pub fn to_p8scii(string: &str) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::new();
+    for ch in string.chars() {
+        if ch.is_ascii() {
+            bytes.push(ch as u8);
+        } else if let Some((_, byte)) = UNICODE.iter().find(|(uni_ch, _)| *uni_ch == ch) {
+            bytes.push(*byte);
+        }
+    }
+    bytes
+}
+
+/// Converts raw bytes from a cart to P8SCII, valid UTF-8 is decoded and anything else is
+/// treated as P8SCII already
+pub fn p8scii_from_bytes(bytes: &[u8]) -> Vec<u8> {
+    match std::str::from_utf8(bytes) {
+        Ok(string) => to_p8scii(string),
+        Err(_) => bytes.to_vec(),
+    }
 }
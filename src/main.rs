@@ -1,5 +1,4 @@
-#![feature(slice_fill)]
-
+extern crate chrono;
 extern crate directories;
 #[macro_use]
 extern crate lazy_static;
@@ -10,25 +9,39 @@ extern crate sdl2;
 extern crate serde_json;
 extern crate wasmtime;
 
+mod audio_export;
 mod cart;
 mod config;
+mod controller;
 mod draw_state;
 mod font;
 mod palette;
+mod pause_menu;
+mod rewind;
 mod runtime;
+mod save_state;
+mod synth;
 mod utils;
 
 // Api
 mod api;
 
+use crate::api::input::MouseState;
+use crate::api::misc::{FrameStats, MenuItem};
 use crate::cart::Cart;
 use crate::config::Config;
-use crate::palette::ColorPalette;
+use crate::controller::{Controllers, PLAYER_COUNT};
+use crate::palette::{ColorPalette, RgbPalette, DEFAULT_RGB_PALETTE};
+use crate::pause_menu::{MenuAction, PauseMenu};
+use crate::rewind::Rewind;
 use crate::runtime::*;
+use crate::save_state::{SaveStates, StateHotkey};
+use crate::synth::{Synth, SynthPlayback};
 use crate::utils::*;
 use rand_pcg::Pcg64Mcg;
-use sdl2::{TimerSubsystem, keyboard::Scancode};
-use sdl2::pixels::Color;
+use sdl2::audio::AudioSpecDesired;
+use sdl2::mouse::MouseButton;
+use sdl2::keyboard::Scancode;
 use sdl2::pixels::PixelFormatEnum;
 use sdl2::{
     event::{Event, WindowEvent},
@@ -36,14 +49,13 @@ use sdl2::{
 };
 use std::convert::From;
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use std::{collections::HashSet, sync::MutexGuard};
 
 const WINDOW_WIDTH: i32 = 512;
 const WINDOW_HEIGHT: i32 = 512;
 const WIDTH: i32 = 128;
 const HEIGHT: i32 = 128;
-const TARGET_FPS: f32 = 30.0;
-const FRAME_LEN_MS: u32 = ((1.0 / TARGET_FPS) * 1000.0) as u32;
 
 #[derive(Copy, Clone, Debug)]
 pub struct TerminalLocation(pub i32, pub i32);
@@ -71,15 +83,28 @@ impl TerminalLocation {
 lazy_static! {
     static ref CONFIG: Mutex<Config> = Mutex::new(Config::get_config_or_create());
     static ref CART: Mutex<Option<Box<dyn Cart>>> = Mutex::new(None);
+    static ref CART_DATA_ID: Mutex<Option<String>> = Mutex::new(None);
     static ref CART_TO_LOAD: Mutex<bool> = Mutex::new(false);
-    static ref KEYSTATE_FRAME: Mutex<HashSet<Scancode>> = Mutex::new(HashSet::new());
+    static ref FRAME_STATS: Mutex<FrameStats> = Mutex::new(FrameStats::default());
+    static ref BUTTON_HOLD: Mutex<[[u32; 6]; PLAYER_COUNT]> = Mutex::new([[0; 6]; PLAYER_COUNT]);
     static ref KEYSTATE_FRAME_FIFO: Mutex<Vec<Scancode>> = Mutex::new(Vec::new());
     static ref KEYSTATE_HELD: Mutex<HashSet<Scancode>> = Mutex::new(HashSet::new());
     static ref MEM: Mutex<[u8; 0x8000]> = Mutex::new([0; 0x8000]);
+    static ref MENU_ITEMS: Mutex<[Option<MenuItem>; 5]> = Mutex::new(Default::default());
+    static ref MOUSE: Mutex<MouseState> = Mutex::new(MouseState::default());
+    static ref PAD_HELD: Mutex<[u8; PLAYER_COUNT]> = Mutex::new([0; PLAYER_COUNT]);
+    static ref RGB_PALETTE: Mutex<RgbPalette> = Mutex::new(DEFAULT_RGB_PALETTE);
     static ref RAND_SRC: Mutex<Pcg64Mcg> = Mutex::new(Pcg64Mcg::new(0xcafef00dbeefd34d));
+    static ref SYNTH: Mutex<Synth> = Mutex::new(Synth::new());
+    static ref TEXT_INPUT: Mutex<Vec<u8>> = Mutex::new(Vec::new());
     static ref TIME: Mutex<f32> = Mutex::new(0.0);
 }
 
+/// Frames per second to run `cart` at, WARS-8 binaries can set their own rate
+fn cart_frame_rate(cart: &dyn Cart, runtime: &dyn Runtime) -> f32 {
+    cart.frame_rate().unwrap_or_else(|| runtime.frame_rate())
+}
+
 pub fn set_pixel(
     mutex_guard: Option<&mut MutexGuard<[u8; 0x8000]>>,
     loc: TerminalLocation,
@@ -95,6 +120,11 @@ pub fn set_pixel(
             }
         };
 
+        let (clip_x0, clip_y0, clip_x1, clip_y1) = draw_state::get_clip(Some(mg));
+        if loc.0 < clip_x0 || loc.1 < clip_y0 || loc.0 >= clip_x1 || loc.1 >= clip_y1 {
+            return;
+        }
+
         let offset = (0x6000 + loc.0 / 2 + (loc.1 * WIDTH / 2)) as usize;
         if loc.0 % 2 == 1 {
             let lower = mg[offset] & 0b1111;
@@ -106,6 +136,36 @@ pub fn set_pixel(
     }
 }
 
+/// Sets a pixel for a shape primitive, `color` holds the primary color in the low nibble and
+/// the secondary color used by set fill pattern bits in the high nibble
+pub fn fill_pixel(
+    mutex_guard: Option<&mut MutexGuard<[u8; 0x8000]>>,
+    loc: TerminalLocation,
+    color: i32,
+) {
+    let mut mutex;
+    let mg = match mutex_guard {
+        Some(mg) => mg,
+        None => {
+            mutex = MEM.lock().unwrap();
+            &mut mutex
+        }
+    };
+
+    let (pattern, transparent) = draw_state::get_fill_pattern(Some(mg));
+    let bit = 15 - (((loc.1 & 0b11) * 4) + (loc.0 & 0b11));
+    let color = if (pattern >> bit) & 0b1 == 0 {
+        color & 0b1111
+    } else if transparent {
+        return;
+    } else {
+        (color >> 4) & 0b1111
+    };
+
+    let color = ColorPalette::from(color).apply_palette_mod(Some(mg), false);
+    set_pixel(Some(mg), loc, color);
+}
+
 pub fn get_pixel(
     mutex_guard: Option<&MutexGuard<[u8; 0x8000]>>,
     loc: TerminalLocation,
@@ -184,7 +244,7 @@ pub fn set_sprite(
 
             let col0 = i32::from(data[y as usize][(x * 2) as usize]) as u8;
             let col1 = i32::from(data[y as usize][((x * 2) + 1) as usize]) as u8;
-            mg[offset as usize] = col0 | (col1 << 4);
+            mg[offset] = col0 | (col1 << 4);
         }
     }
 }
@@ -211,13 +271,40 @@ pub fn get_sprite(
             let col0 = mg[offset as usize] & 0b1111;
             let col1 = (mg[offset as usize] >> 4) & 0b1111;
             sprite[y as usize][(x * 2) as usize] = ColorPalette::from(col0 as i32);
-            sprite[y as usize][(x * 2) as usize] = ColorPalette::from(col1 as i32);
+            sprite[y as usize][((x * 2) + 1) as usize] = ColorPalette::from(col1 as i32);
         }
     }
 
     sprite
 }
 
+/// Reads a single pixel of the spritesheet, pixels outside of it are black
+pub fn get_spritesheet_pixel(
+    mutex_guard: Option<&MutexGuard<[u8; 0x8000]>>,
+    x: i32,
+    y: i32,
+) -> ColorPalette {
+    let mutex;
+    let mg = match mutex_guard {
+        Some(mg) => mg,
+        None => {
+            mutex = MEM.lock().unwrap();
+            &mutex
+        }
+    };
+
+    if x < 0 || y < 0 || x >= 128 || y >= 128 {
+        return ColorPalette::Black;
+    }
+
+    let val = mg[((y * 64) + (x / 2)) as usize];
+    if x % 2 == 1 {
+        ColorPalette::from(((val >> 4) & 0b1111) as i32)
+    } else {
+        ColorPalette::from((val & 0b1111) as i32)
+    }
+}
+
 pub fn set_sprite_flag(
     mutex_guard: Option<&mut MutexGuard<[u8; 0x8000]>>,
     sprite_idx: i32,
@@ -233,7 +320,7 @@ pub fn set_sprite_flag(
         }
     };
 
-    if sprite_idx >= 0 && sprite_idx <= 0xFF {
+    if (0..=0xFF).contains(&sprite_idx) {
         let addr = 0x3000 + sprite_idx as usize;
         if let Some(idx) = flag_idx {
             let idx = idx.max(7);
@@ -263,7 +350,7 @@ pub fn get_sprite_flag(
         }
     };
 
-    if sprite_idx >= 0 && sprite_idx <= 0xFF {
+    if (0..=0xFF).contains(&sprite_idx) {
         let val = mg[0x3000 + sprite_idx as usize];
         if let Some(idx) = flag_idx {
             val >> idx.max(7) & 0b1
@@ -275,7 +362,23 @@ pub fn get_sprite_flag(
     }
 }
 
+/// Bit of `MouseState::buttons` for `button`
+fn mouse_button_bit(button: MouseButton) -> i32 {
+    match button {
+        MouseButton::Left => 0b1,
+        MouseButton::Right => 0b10,
+        MouseButton::Middle => 0b100,
+        _ => 0,
+    }
+}
+
 fn main() {
+    let args = std::env::args().collect::<Vec<String>>();
+    if args.len() > 1 && args[1] == "audio-export" {
+        audio_export::run(&args[2..]);
+        return;
+    }
+
     let mut boot_cart_path;
     if std::env::args().len() == 1 {
         let path_obj =
@@ -315,86 +418,231 @@ fn main() {
         .create_texture_streaming(PixelFormatEnum::RGBX8888, WIDTH as u32, HEIGHT as u32)
         .unwrap();
 
+    let audio_subsystem = sdl_ctx.audio().unwrap();
+    let audio_spec = AudioSpecDesired {
+        freq: Some(synth::SAMPLE_RATE as i32),
+        channels: Some(1),
+        samples: Some(512),
+    };
+    let _audio_device = match audio_subsystem.open_playback(None, &audio_spec, |_| SynthPlayback) {
+        Ok(device) => {
+            device.resume();
+            Some(device)
+        }
+        Err(why) => {
+            println!(
+                "Unable to open audio device, sound disabled. Reason: {}",
+                why
+            );
+            None
+        }
+    };
+
+    let mut controllers = Controllers::new(
+        sdl_ctx.game_controller().unwrap(),
+        &CONFIG.lock().unwrap().controllers,
+    );
+
     let mut cart_pre_mutex = CART.lock().unwrap();
-    *cart_pre_mutex = Some(Cart::load(&boot_cart_path));
+    *cart_pre_mutex = Some(<dyn Cart>::load(&boot_cart_path));
     let mut runtime = cart_pre_mutex.as_deref().unwrap().create_runtime();
+    let mut target_fps = cart_frame_rate(cart_pre_mutex.as_deref().unwrap(), &*runtime);
+    palette::load_rgb_palette(cart_pre_mutex.as_deref().unwrap().palette());
     drop(cart_pre_mutex);
 
+    let user_rgb_palette = CONFIG
+        .lock()
+        .unwrap()
+        .video
+        .palette
+        .as_ref()
+        .map_or([None; 32], |colors| palette::parse_rgb_palette(colors));
+
     draw_state::reset(None);
     runtime.init();
 
     let mut cart_start_offset: f32 = 0.0;
     let mut sdl_timer = sdl_ctx.timer().unwrap();
-    let mut target_ms = sdl_timer.ticks() + FRAME_LEN_MS;
+    // Frame deadlines are counted from the start so rounding never adds up
+    let mut pacing_start = Instant::now();
+    let mut paced_frames: u32 = 0;
     let mut fps_counter = FpsCounter::new(sdl_timer.ticks());
-    let mut paused = false;
+    let mut pause_menu = PauseMenu::new();
+    let mut save_states = SaveStates::new();
+    let (rewind_key, rewind_settings) = {
+        let config = CONFIG.lock().unwrap();
+        (config.keys.rewind, config.rewind.clone())
+    };
+    let mut rewind = Rewind::new(rewind_settings);
+    let mut mouse_locked = false;
     'sdlloop: loop {
         let mut cart_mutex = CART.lock().unwrap();
         let mut cart_to_load_mutex = CART_TO_LOAD.lock().unwrap();
-        if cart_mutex.is_none() || *cart_to_load_mutex == true {
+        let reload = cart_mutex.is_none() || *cart_to_load_mutex;
+        if reload {
+            pause_menu.close();
+            SYNTH.lock().unwrap().reset();
+            TEXT_INPUT.lock().unwrap().clear();
+            api::misc::clear_menu_items();
+            api::misc::close_cartdata();
+            rewind.clear();
+
             let mut mem = MEM.lock().unwrap();
             mem.fill(0);
-
             draw_state::reset(Some(&mut mem));
-
+            drop(mem);
+
+            // Carts write their data to memory while loading, so load them again after clearing it
+            let path = match cart_mutex.as_deref() {
+                Some(cart) => cart.path().clone(),
+                None => {
+                    println!("Resetting to boot cartridge");
+                    boot_cart_path.clone()
+                }
+            };
             *cart_to_load_mutex = false;
-            if cart_mutex.is_none() {
-                println!("Resetting to boot cartridge");
-                *cart_mutex = Some(Cart::load(&boot_cart_path));
-            }
+            *cart_mutex = Some(<dyn Cart>::load(&path));
 
             cart_start_offset = sdl_timer.ticks() as f32 / 1000.0;
-            let mut time = TIME.lock().unwrap();
-            *time = (sdl_timer.ticks() as f32 / 1000.0) - cart_start_offset;
+            *TIME.lock().unwrap() = (sdl_timer.ticks() as f32 / 1000.0) - cart_start_offset;
 
             runtime = cart_mutex.as_deref().unwrap().create_runtime();
-            runtime.init();
+            target_fps = cart_frame_rate(cart_mutex.as_deref().unwrap(), &*runtime);
+            pacing_start = Instant::now();
+            paced_frames = 0;
+            palette::load_rgb_palette(cart_mutex.as_deref().unwrap().palette());
         }
 
         drop(cart_mutex);
         drop(cart_to_load_mutex);
 
-        runtime.update();
-        runtime.draw();
+        if reload {
+            runtime.init();
+        }
 
-        let config = CONFIG.lock().unwrap();
-        let mut keystate_frame = KEYSTATE_FRAME.lock().unwrap();
+        let rewind_held = KEYSTATE_HELD
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|kc| *kc as i32 == rewind_key);
+
+        let frame_start = Instant::now();
+        let mut ran_cart = false;
+        if pause_menu.is_open() {
+            pause_menu.draw();
+        } else if !rewind.step_back(rewind_held, &mut *runtime) {
+            runtime.update();
+            runtime.draw();
+            ran_cart = true;
+        }
+        let cart_time = frame_start.elapsed();
+        if ran_cart {
+            rewind.capture(&mut *runtime, target_fps);
+        }
+
+        let mut config = CONFIG.lock().unwrap();
         let mut keystate_frame_fifo = KEYSTATE_FRAME_FIFO.lock().unwrap();
         let mut keystate_held = KEYSTATE_HELD.lock().unwrap();
+        let devkit_mode = api::input::devkit_mode();
+        let mut text_input = TEXT_INPUT.lock().unwrap();
+        let mut mouse = MOUSE.lock().unwrap();
+
+        mouse.wheel = 0;
+        mouse.rel_x = 0;
+        mouse.rel_y = 0;
+
+        if api::input::mouse_lock() != mouse_locked {
+            mouse_locked = !mouse_locked;
+            sdl_ctx.mouse().set_relative_mouse_mode(mouse_locked);
+        }
 
-        keystate_frame.clear();
         keystate_frame_fifo.clear();
 
+        // Menu actions can call into the cart, so they wait until the locks are dropped
+        let mut menu_action = MenuAction::None;
+        let mut state_hotkey = None;
+        let mut keys_pressed = HashSet::new();
         for event in sdl_ctx.event_pump().unwrap().poll_iter() {
+            controllers.handle_event(&event, &config.controllers);
             match event {
                 Event::Quit { .. } => {
                     break 'sdlloop;
                 }
+
+                // The open menu sees keys first so quit and pause can cancel a rebind
+                Event::KeyDown {
+                    scancode: Some(kc),
+                    repeat,
+                    ..
+                } if pause_menu.is_open() => {
+                    if !repeat {
+                        menu_action = pause_menu.key_pressed(kc, &mut config);
+                    }
+                }
+
                 Event::KeyDown {
                     scancode: Some(kc), ..
                 } if kc as i32 == config.keys.quit => {
-                    let mut cart_mutex = CART.lock().unwrap();
-                    let mut cart_to_load_mutex = CART_TO_LOAD.lock().unwrap();
-                    *cart_to_load_mutex = true;
-                    *cart_mutex = Some(Cart::load(&boot_cart_path));
+                    api::misc::unload();
                 }
                 Event::KeyDown {
                     scancode: Some(kc), ..
                 } if kc as i32 == config.keys.pause => {
-                    paused = !paused;
+                    pause_menu.pause_pressed();
                 }
 
                 Event::KeyDown {
-                    scancode: Some(kc), ..
+                    scancode: Some(kc),
+                    repeat: false,
+                    ..
+                } if kc as i32 == config.keys.save_state => {
+                    state_hotkey = Some(StateHotkey::Save);
+                }
+                Event::KeyDown {
+                    scancode: Some(kc),
+                    repeat: false,
+                    ..
+                } if kc as i32 == config.keys.load_state => {
+                    state_hotkey = Some(StateHotkey::Load);
+                }
+                Event::KeyDown {
+                    scancode: Some(kc),
+                    repeat: false,
+                    ..
+                } if kc as i32 == config.keys.state_slot => {
+                    state_hotkey = Some(StateHotkey::NextSlot);
+                }
+
+                Event::KeyDown {
+                    scancode: Some(kc),
+                    repeat,
+                    ..
                 } => {
-                    keystate_frame.insert(kc);
                     if !keystate_frame_fifo.contains(&kc) {
                         keystate_frame_fifo.push(kc);
                     }
 
+                    if !repeat {
+                        keys_pressed.insert(kc);
+                    }
+
                     if !keystate_held.contains(&kc) {
                         keystate_held.insert(kc);
                     }
+
+                    // Text input events don't include editing keys, pass them to devkit carts
+                    if devkit_mode {
+                        match kc {
+                            Scancode::Return | Scancode::KpEnter => text_input.push(b'\r'),
+                            Scancode::Backspace => text_input.push(8),
+                            Scancode::Tab => text_input.push(b'\t'),
+                            _ => {}
+                        }
+                    }
+                }
+
+                Event::TextInput { text, .. } if devkit_mode => {
+                    text_input.extend(font::to_p8scii(&text));
                 }
 
                 Event::KeyUp {
@@ -403,24 +651,84 @@ fn main() {
                     keystate_held.remove(&kc);
                 }
 
-                Event::Window { win_event, .. } => {
-                    if let WindowEvent::Resized(new_x, new_y) = win_event {
-                        let screen_size_scaled = new_x.min(new_y);
-                        let x = (((new_x - screen_size_scaled) >> 1) << 1) / 2;
-                        let y = (((new_y - screen_size_scaled) >> 1) << 1) / 2;
-                        out_win_rect =
-                            Rect::new(x, y, screen_size_scaled as u32, screen_size_scaled as u32);
-                    }
+                Event::MouseMotion {
+                    x, y, xrel, yrel, ..
+                } => {
+                    // Map window coordinates back through the letterboxed output rect
+                    let out_width = (out_win_rect.width() as i32).max(1);
+                    let out_height = (out_win_rect.height() as i32).max(1);
+                    mouse.x = ((x - out_win_rect.x()) * WIDTH / out_width).clamp(0, WIDTH - 1);
+                    mouse.y = ((y - out_win_rect.y()) * HEIGHT / out_height).clamp(0, HEIGHT - 1);
+                    mouse.rel_x += xrel;
+                    mouse.rel_y += yrel;
+                }
+
+                Event::MouseButtonDown { mouse_btn, .. } => {
+                    mouse.buttons |= mouse_button_bit(mouse_btn);
+                }
+
+                Event::MouseButtonUp { mouse_btn, .. } => {
+                    mouse.buttons &= !mouse_button_bit(mouse_btn);
+                }
+
+                Event::MouseWheel { y, .. } => {
+                    mouse.wheel += y;
+                }
+
+                Event::Window {
+                    win_event: WindowEvent::Resized(new_x, new_y),
+                    ..
+                } => {
+                    let screen_size_scaled = new_x.min(new_y);
+                    let x = (((new_x - screen_size_scaled) >> 1) << 1) / 2;
+                    let y = (((new_y - screen_size_scaled) >> 1) << 1) / 2;
+                    out_win_rect =
+                        Rect::new(x, y, screen_size_scaled as u32, screen_size_scaled as u32);
                 }
 
                 _ => {}
             }
         }
 
+        let mut pad_held = PAD_HELD.lock().unwrap();
+        *pad_held = controllers.held(&config.controllers);
+        api::input::update_button_hold(
+            &config.keys,
+            &keystate_held,
+            &pad_held,
+            &keys_pressed,
+            &controllers.take_pressed(),
+        );
+
+        drop(pad_held);
         drop(config);
         drop(keystate_held);
-        drop(keystate_frame);
         drop(keystate_frame_fifo);
+        drop(text_input);
+        drop(mouse);
+
+        match menu_action {
+            MenuAction::None => {}
+            MenuAction::MenuItem(item, buttons) => {
+                pause_menu.close();
+                if runtime.menuitem(&item, buttons) {
+                    pause_menu.reopen();
+                }
+            }
+            MenuAction::ResetCart => *CART_TO_LOAD.lock().unwrap() = true,
+            MenuAction::BackToBoot | MenuAction::Quit => api::misc::unload(),
+        }
+
+        if let Some(hotkey) = state_hotkey {
+            save_states.handle(hotkey, &mut *runtime);
+        }
+
+        let mut rgb_palette = *RGB_PALETTE.lock().unwrap();
+        for (color, user_color) in rgb_palette.iter_mut().zip(user_rgb_palette.iter()) {
+            if let Some(user_color) = user_color {
+                *color = *user_color;
+            }
+        }
 
         texture
             .with_lock(None, |buffer: &mut [u8], _pitch: usize| {
@@ -429,7 +737,11 @@ fn main() {
                     for x in 0..WIDTH {
                         let loc = TerminalLocation(x, y);
                         let raw_idx = usize::from(loc) * 4;
-                        let color = Color::from(get_pixel(Some(&memory), loc));
+                        let col = i32::from(get_pixel(Some(&memory), loc)) as u8;
+                        let color = palette::screen_rgb(
+                            &rgb_palette,
+                            draw_state::get_screen_palette_entry(Some(&memory), col),
+                        );
                         buffer[raw_idx + 1] = color.b;
                         buffer[raw_idx + 2] = color.g;
                         buffer[raw_idx + 3] = color.r;
@@ -442,10 +754,25 @@ fn main() {
         canvas.copy(&texture, None, Some(out_win_rect)).unwrap();
         canvas.present();
 
-        let frame_difference = target_ms as i32 - sdl_timer.ticks() as i32;
-        target_ms += FRAME_LEN_MS;
-        if frame_difference > 0 {
-            sdl_timer.delay(frame_difference as u32);
+        let frame_len = Duration::from_secs_f64(1.0 / target_fps as f64);
+        let mut frame_stats = FRAME_STATS.lock().unwrap();
+        frame_stats.memory = runtime.memory_usage();
+        frame_stats.cpu = cart_time.as_secs_f32() / frame_len.as_secs_f32();
+        frame_stats.system_cpu = frame_start.elapsed().as_secs_f32() / frame_len.as_secs_f32();
+        frame_stats.fps = fps_counter.fps();
+        frame_stats.target_fps = target_fps as u32;
+        drop(frame_stats);
+
+        paced_frames += 1;
+        let deadline =
+            pacing_start + Duration::from_secs_f64(paced_frames as f64 / target_fps as f64);
+        let now = Instant::now();
+        if deadline > now {
+            std::thread::sleep(deadline - now);
+        } else if now - deadline > frame_len {
+            // Too far behind to catch up without rushing frames, pace from now instead
+            pacing_start = now;
+            paced_frames = 0;
         }
         fps_counter.tick(sdl_timer.ticks());
 
@@ -453,5 +780,6 @@ fn main() {
         *time = (sdl_timer.ticks() as f32 / 1000.0) - cart_start_offset;
     }
 
+    api::misc::flush_cartdata();
     std::fs::write("./lastmem.bin", *MEM.lock().unwrap()).unwrap();
 }
@@ -2,7 +2,7 @@ use std::sync::MutexGuard;
 
 use sdl2::pixels::Color;
 
-use crate::draw_state;
+use crate::{draw_state, RGB_PALETTE};
 
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
 pub enum ColorPalette {
@@ -71,27 +71,89 @@ impl From<ColorPalette> for i32 {
     }
 }
 
+/// RGB values of the 16 standard colors followed by the 16 extended colors
+pub type RgbPalette = [(u8, u8, u8); 32];
+
+pub const DEFAULT_RGB_PALETTE: RgbPalette = [
+    (0x00, 0x00, 0x00),
+    (0x1D, 0x2B, 0x53),
+    (0x7E, 0x25, 0x53),
+    (0x00, 0x87, 0x51),
+    (0xAB, 0x52, 0x36),
+    (0x5F, 0x57, 0x4F),
+    (0xC2, 0xC3, 0xC7),
+    (0xFF, 0xF1, 0xE8),
+    (0xFF, 0x00, 0x4D),
+    (0xFF, 0xA3, 0x00),
+    (0xFF, 0xEC, 0x27),
+    (0x00, 0xE4, 0x36),
+    (0x29, 0xAD, 0xFF),
+    (0x83, 0x76, 0x9C),
+    (0xFF, 0x77, 0xA8),
+    (0xFF, 0xCC, 0xAA),
+    (0x29, 0x18, 0x14),
+    (0x11, 0x1D, 0x35),
+    (0x42, 0x21, 0x36),
+    (0x12, 0x53, 0x59),
+    (0x74, 0x2F, 0x29),
+    (0x49, 0x33, 0x3B),
+    (0xA2, 0x88, 0x79),
+    (0xF3, 0xEF, 0x7D),
+    (0xBE, 0x12, 0x50),
+    (0xFF, 0x6C, 0x24),
+    (0xA8, 0xE7, 0x2E),
+    (0x00, 0xB5, 0x43),
+    (0x06, 0x5A, 0xB5),
+    (0x75, 0x46, 0x65),
+    (0xFF, 0x6E, 0x59),
+    (0xFF, 0x9D, 0x81),
+];
+
 impl From<ColorPalette> for Color {
     fn from(color: ColorPalette) -> Self {
-        match color {
-            ColorPalette::Black => Color::from((0x00, 0x00, 0x00)),
-            ColorPalette::DarkBlue => Color::from((0x1D, 0x2B, 0x53)),
-            ColorPalette::DarkPurple => Color::from((0x7E, 0x25, 0x53)),
-            ColorPalette::DarkGreen => Color::from((0x00, 0x87, 0x51)),
-            ColorPalette::Brown => Color::from((0xAB, 0x52, 0x36)),
-            ColorPalette::DarkGray => Color::from((0x5F, 0x57, 0x4F)),
-            ColorPalette::LightGray => Color::from((0xC2, 0xC3, 0xC7)),
-            ColorPalette::White => Color::from((0xFF, 0xF1, 0xE8)),
-            ColorPalette::Red => Color::from((0xFF, 0x00, 0x4D)),
-            ColorPalette::Orange => Color::from((0xFF, 0xA3, 0x00)),
-            ColorPalette::Yellow => Color::from((0xFF, 0xEC, 0x27)),
-            ColorPalette::Green => Color::from((0x00, 0xE4, 0x36)),
-            ColorPalette::Blue => Color::from((0x29, 0xAD, 0xFF)),
-            ColorPalette::Indigo => Color::from((0x83, 0x76, 0x9C)),
-            ColorPalette::Pink => Color::from((0xFF, 0x77, 0xA8)),
-            ColorPalette::Peach => Color::from((0xFF, 0xCC, 0xAA)),
+        Color::from(DEFAULT_RGB_PALETTE[i32::from(color) as usize])
+    }
+}
+
+/// Maps a screen palette entry (0-15 or the extended colors 128-143) to its RGB palette index
+pub fn rgb_palette_index(val: u8) -> usize {
+    if val & 0x80 == 0 {
+        (val & 0b1111) as usize
+    } else {
+        16 + (val & 0b1111) as usize
+    }
+}
+
+/// Converts a screen palette entry to RGB using `rgb_palette`
+pub fn screen_rgb(rgb_palette: &RgbPalette, val: u8) -> Color {
+    Color::from(rgb_palette[rgb_palette_index(val)])
+}
+
+/// Parses `#RRGGBB` strings into per entry RGB overrides, missing or invalid entries are `None`
+pub fn parse_rgb_palette(colors: &[String]) -> [Option<(u8, u8, u8)>; 32] {
+    let mut rgb_palette = [None; 32];
+    for (idx, color) in colors.iter().take(32).enumerate() {
+        let hex = color.trim_start_matches('#');
+        match u32::from_str_radix(hex, 16) {
+            Ok(rgb) if hex.len() == 6 => {
+                rgb_palette[idx] = Some(((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8));
+            }
+            _ => println!(
+                "Invalid palette color {} for color {}, ignoring",
+                color, idx
+            ),
         }
     }
+    rgb_palette
+}
+
+/// Resets the display RGB palette to the default with `colors` replacing the first entries
+pub fn load_rgb_palette(colors: &[(u8, u8, u8)]) {
+    let mut rgb_palette = RGB_PALETTE.lock().unwrap();
+    *rgb_palette = DEFAULT_RGB_PALETTE;
+    for (idx, color) in colors.iter().take(32).enumerate() {
+        rgb_palette[idx] = *color;
+    }
 }
 
 impl ColorPalette {
@@ -0,0 +1,405 @@
+use sdl2::keyboard::Scancode;
+
+use crate::api::misc::MenuItem;
+use crate::config::{Config, PlayerKeyBindings};
+use crate::controller::PLAYER_COUNT;
+use crate::{api, draw_state, font, MEM, MENU_ITEMS};
+
+const BUTTON_NAMES: [&str; 6] = ["left", "right", "up", "down", "o", "x"];
+
+/// What the main loop has to do after a key press in the menu
+pub enum MenuAction {
+    None,
+    /// Call a cart menu item callback with the buttons pressed
+    MenuItem(MenuItem, i32),
+    ResetCart,
+    BackToBoot,
+    /// The quit key was pressed outside of a rebind
+    Quit,
+}
+
+enum MainEntry {
+    Continue,
+    Cart(MenuItem),
+    Controls,
+    ResetCart,
+    BackToBoot,
+}
+
+impl MainEntry {
+    fn label(&self) -> String {
+        match self {
+            MainEntry::Continue => "continue".to_string(),
+            MainEntry::Cart(item) => item.label.clone(),
+            MainEntry::Controls => "controls".to_string(),
+            MainEntry::ResetCart => "reset cart".to_string(),
+            MainEntry::BackToBoot => "back to boot".to_string(),
+        }
+    }
+}
+
+enum Screen {
+    Main,
+    Controls,
+    /// Waiting for a key for `button` of `player`, `pending` holds the keys chosen so far
+    Rebind {
+        player: usize,
+        button: usize,
+        pending: [i32; 6],
+    },
+}
+
+/// Overlay opened with the pause key, the cart is suspended while it is open
+pub struct PauseMenu {
+    screen: Option<Screen>,
+    selected: usize,
+    message: Option<String>,
+    /// Console memory when the menu was opened, restored when it is closed
+    saved_mem: Box<[u8; 0x8000]>,
+}
+
+impl PauseMenu {
+    pub fn new() -> Self {
+        PauseMenu {
+            screen: None,
+            selected: 0,
+            message: None,
+            saved_mem: Box::new([0; 0x8000]),
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.screen.is_some()
+    }
+
+    pub fn open(&mut self) {
+        self.saved_mem.copy_from_slice(&MEM.lock().unwrap()[..]);
+        self.show(Screen::Main);
+    }
+
+    /// Opens the main screen again after a menu item callback asked to stay open
+    pub fn reopen(&mut self) {
+        let selected = self.selected;
+        self.open();
+        self.selected = selected.min(self.items().len() - 1);
+    }
+
+    pub fn close(&mut self) {
+        if self.screen.take().is_some() {
+            MEM.lock().unwrap().copy_from_slice(&self.saved_mem[..]);
+        }
+    }
+
+    /// Handles the pause key, which backs out of the current screen
+    pub fn pause_pressed(&mut self) {
+        match self.screen {
+            None => self.open(),
+            Some(Screen::Main) => self.close(),
+            Some(Screen::Controls) => self.show(Screen::Main),
+            Some(Screen::Rebind { player, .. }) => {
+                self.show(Screen::Controls);
+                self.selected = player;
+            }
+        }
+    }
+
+    fn show(&mut self, screen: Screen) {
+        self.screen = Some(screen);
+        self.selected = 0;
+        self.message = None;
+    }
+
+    /// Entries of the main screen, the cart's menu items come after continue
+    fn main_entries() -> Vec<MainEntry> {
+        let mut entries = vec![MainEntry::Continue];
+        for item in MENU_ITEMS.lock().unwrap().iter().flatten() {
+            entries.push(MainEntry::Cart(item.clone()));
+        }
+        entries.push(MainEntry::Controls);
+        entries.push(MainEntry::ResetCart);
+        entries.push(MainEntry::BackToBoot);
+        entries
+    }
+
+    fn items(&self) -> Vec<String> {
+        match self.screen {
+            Some(Screen::Main) => PauseMenu::main_entries()
+                .iter()
+                .map(|entry| entry.label())
+                .collect(),
+            Some(Screen::Controls) => (1..=PLAYER_COUNT)
+                .map(|player| format!("player {}", player))
+                .chain(std::iter::once("back".to_string()))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Handles a key press while the menu is open
+    pub fn key_pressed(&mut self, scancode: Scancode, config: &mut Config) -> MenuAction {
+        let kc = scancode as i32;
+        if kc == config.keys.pause {
+            self.pause_pressed();
+            return MenuAction::None;
+        }
+
+        // Quit cancels a rebind like pause instead of unloading the cart and its pending keys
+        if let Some(Screen::Rebind { .. }) = self.screen {
+            if kc == config.keys.quit {
+                self.pause_pressed();
+            } else {
+                self.rebind(kc, config);
+            }
+            return MenuAction::None;
+        }
+
+        if kc == config.keys.quit {
+            return MenuAction::Quit;
+        }
+
+        // Navigation uses player 1's bindings as well as the arrow keys and enter
+        let player1 = config.keys.players.first();
+        let bound = |button: i32| player1.and_then(|p| p.button(button)) == Some(kc);
+        let item_count = self.items().len();
+
+        // Buttons passed to menu item callbacks use the PICO-8 bitfield
+        let buttons = if scancode == Scancode::Left || bound(0) {
+            0b1
+        } else if scancode == Scancode::Right || bound(1) {
+            0b10
+        } else if bound(4) {
+            0b1_0000
+        } else if scancode == Scancode::Return || bound(5) {
+            0b10_0000
+        } else {
+            0
+        };
+
+        if scancode == Scancode::Up || bound(2) {
+            self.selected = (self.selected + item_count - 1) % item_count;
+        } else if scancode == Scancode::Down || bound(3) {
+            self.selected = (self.selected + 1) % item_count;
+        } else if buttons != 0 {
+            return self.select(config, buttons);
+        }
+
+        MenuAction::None
+    }
+
+    fn select(&mut self, config: &Config, buttons: i32) -> MenuAction {
+        let selected = self.selected;
+        let confirm = buttons & 0b11_0000 != 0;
+        match self.screen {
+            Some(Screen::Main) => match PauseMenu::main_entries().remove(selected) {
+                MainEntry::Cart(item) => return MenuAction::MenuItem(item, buttons),
+                _ if !confirm => {}
+                MainEntry::Continue => self.close(),
+                MainEntry::Controls => self.show(Screen::Controls),
+                MainEntry::ResetCart => return MenuAction::ResetCart,
+                MainEntry::BackToBoot => return MenuAction::BackToBoot,
+            },
+            Some(Screen::Controls) if !confirm => {}
+            Some(Screen::Controls) if selected < PLAYER_COUNT => {
+                let player = selected;
+                let pending = match config.keys.players.get(player) {
+                    Some(bindings) => [
+                        bindings.left,
+                        bindings.right,
+                        bindings.up,
+                        bindings.down,
+                        bindings.o,
+                        bindings.x,
+                    ],
+                    None => [-1; 6],
+                };
+
+                self.show(Screen::Rebind {
+                    player,
+                    button: 0,
+                    pending,
+                });
+            }
+            Some(Screen::Controls) => self.show(Screen::Main),
+            _ => {}
+        }
+
+        MenuAction::None
+    }
+
+    /// Finds what else `kc` is bound to, ignoring the buttons of `player` not rebound yet
+    fn conflict(config: &Config, player: usize, pending: &[i32], kc: i32) -> Option<String> {
+        if kc == config.keys.save_state {
+            return Some("save state".to_string());
+        }
+        if kc == config.keys.load_state {
+            return Some("load state".to_string());
+        }
+        if kc == config.keys.state_slot {
+            return Some("state slot".to_string());
+        }
+        if kc == config.keys.rewind {
+            return Some("rewind".to_string());
+        }
+
+        if let Some(button) = pending.iter().position(|key| *key == kc) {
+            return Some(format!("p{} {}", player + 1, BUTTON_NAMES[button]));
+        }
+
+        for (other, bindings) in config.keys.players.iter().enumerate() {
+            if other == player {
+                continue;
+            }
+
+            for (button, name) in BUTTON_NAMES.iter().enumerate() {
+                if bindings.button(button as i32) == Some(kc) {
+                    return Some(format!("p{} {}", other + 1, name));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn rebind(&mut self, kc: i32, config: &mut Config) {
+        let (player, button, mut pending) = match self.screen {
+            Some(Screen::Rebind {
+                player,
+                button,
+                pending,
+            }) => (player, button, pending),
+            _ => return,
+        };
+
+        if let Some(used_by) = PauseMenu::conflict(config, player, &pending[..button], kc) {
+            self.message = Some(format!("already used by {}", used_by));
+            return;
+        }
+
+        pending[button] = kc;
+        if button + 1 < BUTTON_NAMES.len() {
+            self.screen = Some(Screen::Rebind {
+                player,
+                button: button + 1,
+                pending,
+            });
+            self.message = None;
+            return;
+        }
+
+        // Players without bindings before this one are left unbound
+        while config.keys.players.len() <= player {
+            config.keys.players.push(PlayerKeyBindings {
+                left: -1,
+                right: -1,
+                up: -1,
+                down: -1,
+                o: -1,
+                x: -1,
+            });
+        }
+
+        config.keys.players[player] = PlayerKeyBindings {
+            left: pending[0],
+            right: pending[1],
+            up: pending[2],
+            down: pending[3],
+            o: pending[4],
+            x: pending[5],
+        };
+        config.save_config();
+
+        self.show(Screen::Controls);
+        self.selected = player;
+        self.message = Some(format!("player {} saved", player + 1));
+    }
+
+    /// Draws the menu over the screen the cart had when it was opened
+    pub fn draw(&self) {
+        {
+            let mut mem = MEM.lock().unwrap();
+            mem.copy_from_slice(&self.saved_mem[..]);
+            draw_state::reset_palette(Some(&mut mem), true);
+            draw_state::set_camera_offset(Some(&mut mem), Some(0), Some(0));
+            draw_state::set_clip(Some(&mut mem), 0, 0, 128, 128);
+            draw_state::set_fill_pattern(Some(&mut mem), 0, false);
+        }
+
+        let mut lines = match self.screen {
+            Some(Screen::Rebind { player, button, .. }) => vec![
+                "press a key for".to_string(),
+                format!("p{} {}", player + 1, BUTTON_NAMES[button]),
+            ],
+            _ => self.items(),
+        };
+        if let Some(message) = &self.message {
+            lines.push(message.clone());
+        }
+
+        let width = lines.iter().map(|line| line.len()).max().unwrap_or(0) as i32 * 4 + 12;
+        let height = lines.len() as i32 * 8 + 6;
+        let x0 = (128 - width) / 2;
+        let y0 = (128 - height) / 2;
+        api::gfx::rectfill(x0, y0, x0 + width - 1, y0 + height - 1, 0);
+        api::gfx::rect(x0, y0, x0 + width - 1, y0 + height - 1, 7);
+
+        let selectable = match self.screen {
+            Some(Screen::Rebind { .. }) => 0,
+            _ => self.items().len(),
+        };
+        for (idx, line) in lines.iter().enumerate() {
+            let y = y0 + 4 + (idx as i32 * 8);
+            let is_message = self.message.is_some() && idx == lines.len() - 1;
+            let col = match idx < selectable && idx == self.selected {
+                true => 7,
+                false if is_message => 9,
+                false => 6,
+            };
+
+            if idx < selectable && idx == self.selected {
+                api::gfx::print(&font::to_p8scii(">"), x0 + 3, y, 7);
+            }
+            api::gfx::print(&font::to_p8scii(line), x0 + 8, y, col);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MenuAction, PauseMenu, Screen};
+    use crate::config::Config;
+    use sdl2::keyboard::Scancode;
+
+    fn rebinding(config: &Config) -> PauseMenu {
+        let bindings = &config.keys.players[1];
+        let mut menu = PauseMenu::new();
+        menu.screen = Some(Screen::Rebind {
+            player: 1,
+            button: 2,
+            pending: [bindings.left, bindings.right, -1, -1, -1, -1],
+        });
+        menu
+    }
+
+    #[test]
+    fn quit_and_pause_cancel_a_rebind() {
+        let mut config = Config::new();
+        let before = config.keys.players[1].up;
+        for key in [Scancode::Escape, Scancode::Grave] {
+            let mut menu = rebinding(&config);
+            let action = menu.key_pressed(key, &mut config);
+            assert!(matches!(action, MenuAction::None));
+            assert!(matches!(menu.screen, Some(Screen::Controls)));
+            assert_eq!(menu.selected, 1);
+            assert_eq!(config.keys.players[1].up, before);
+        }
+    }
+
+    #[test]
+    fn quit_outside_a_rebind_unloads() {
+        let mut config = Config::new();
+        let mut menu = PauseMenu::new();
+        menu.screen = Some(Screen::Controls);
+        let action = menu.key_pressed(Scancode::Escape, &mut config);
+        assert!(matches!(action, MenuAction::Quit));
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::VecDeque;
+use std::io::{Cursor, Read};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::config::RewindSettings;
+use crate::runtime::Runtime;
+
+/// Encodes `to` against `from` as runs of unchanged bytes followed by runs of new bytes
+fn delta(from: &[u8], to: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.write_u32::<LittleEndian>(to.len() as u32).unwrap();
+
+    let mut idx = 0;
+    while idx < to.len() {
+        let same_start = idx;
+        while idx < to.len() && from.get(idx) == Some(&to[idx]) {
+            idx += 1;
+        }
+        let changed_start = idx;
+        while idx < to.len() && from.get(idx) != Some(&to[idx]) {
+            idx += 1;
+        }
+
+        out.write_u32::<LittleEndian>((changed_start - same_start) as u32)
+            .unwrap();
+        out.write_u32::<LittleEndian>((idx - changed_start) as u32)
+            .unwrap();
+        out.extend_from_slice(&to[changed_start..idx]);
+    }
+
+    out
+}
+
+/// Rebuilds the state a `delta` against `from` was made from
+fn apply_delta(from: &[u8], delta: &[u8]) -> Vec<u8> {
+    let mut reader = Cursor::new(delta);
+    let len = reader.read_u32::<LittleEndian>().unwrap() as usize;
+    let mut out = Vec::with_capacity(len);
+    while out.len() < len {
+        let same = reader.read_u32::<LittleEndian>().unwrap() as usize;
+        let changed = reader.read_u32::<LittleEndian>().unwrap() as usize;
+        let start = out.len();
+        out.extend_from_slice(&from[start..start + same]);
+
+        let start = out.len();
+        out.resize(start + changed, 0);
+        reader.read_exact(&mut out[start..]).unwrap();
+    }
+
+    out
+}
+
+/// Ring buffer of the last few seconds of a cart's states. The newest state is kept whole and
+/// each older one as a delta against the state after it, so the oldest can be dropped freely
+pub struct Rewind {
+    settings: RewindSettings,
+    latest: Option<Vec<u8>>,
+    deltas: VecDeque<Vec<u8>>,
+    frame: u32,
+    /// Why the cart's state can't be captured, if it can't
+    unsupported: Option<String>,
+    held: bool,
+}
+
+impl Rewind {
+    pub fn new(settings: RewindSettings) -> Self {
+        Rewind {
+            settings,
+            latest: None,
+            deltas: VecDeque::new(),
+            frame: 0,
+            unsupported: None,
+            held: false,
+        }
+    }
+
+    /// Forgets the buffered states, used when a cart is loaded
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+        self.frame = 0;
+        self.unsupported = None;
+    }
+
+    /// Captures the cart's state every `interval` frames, keeping `seconds` worth at `fps`
+    pub fn capture(&mut self, runtime: &mut dyn Runtime, fps: f32) {
+        if !self.settings.enabled || self.unsupported.is_some() {
+            return;
+        }
+
+        self.frame += 1;
+        let interval = self.settings.interval.max(1);
+        if self.frame < interval {
+            return;
+        }
+        self.frame = 0;
+
+        let state = match runtime.save_state() {
+            Ok(state) => state,
+            Err(why) => {
+                self.unsupported = Some(why);
+                return;
+            }
+        };
+
+        if let Some(latest) = self.latest.as_ref() {
+            self.deltas.push_back(delta(&state, latest));
+        }
+        self.latest = Some(state);
+
+        let capacity = (self.settings.seconds * fps / interval as f32).max(1.0) as usize;
+        while self.deltas.len() > capacity {
+            self.deltas.pop_front();
+        }
+    }
+
+    /// Steps back one capture while `held`, returns true if the cart shouldn't run this frame
+    pub fn step_back(&mut self, held: bool, runtime: &mut dyn Runtime) -> bool {
+        let pressed = held && !self.held;
+        self.held = held;
+        if !held || !self.settings.enabled {
+            return false;
+        }
+
+        if let Some(why) = self.unsupported.as_ref() {
+            if pressed {
+                println!("Unable to rewind. Reason: {}", why);
+            }
+            return false;
+        }
+
+        // Stay on the oldest state until the key is released
+        let (latest, delta) = match (self.latest.as_ref(), self.deltas.pop_back()) {
+            (Some(latest), Some(delta)) => (latest, delta),
+            _ => return true,
+        };
+
+        let state = apply_delta(latest, &delta);
+        if let Err(why) = runtime.load_state(&state) {
+            println!("Unable to rewind. Reason: {}", why);
+            self.clear();
+            return false;
+        }
+        self.latest = Some(state);
+        self.frame = 0;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_delta, delta};
+
+    fn round_trip(from: &[u8], to: &[u8]) {
+        assert_eq!(apply_delta(from, &delta(from, to)), to);
+    }
+
+    #[test]
+    fn equal_buffers() {
+        let state: Vec<u8> = (0..=255).collect();
+        round_trip(&state, &state);
+        // Only the length and a single unchanged run
+        assert_eq!(delta(&state, &state).len(), 12);
+    }
+
+    #[test]
+    fn empty_buffers() {
+        round_trip(&[], &[]);
+        round_trip(&[1, 2, 3], &[]);
+        round_trip(&[], &[1, 2, 3]);
+    }
+
+    #[test]
+    fn grown_to() {
+        round_trip(&[1, 2, 3], &[1, 2, 3, 4, 5]);
+        round_trip(&[1, 2, 3], &[9, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn shrunk_to() {
+        round_trip(&[1, 2, 3, 4, 5], &[1, 2, 3]);
+        round_trip(&[1, 2, 3, 4, 5], &[1, 9]);
+    }
+
+    #[test]
+    fn change_at_tail() {
+        let from = vec![7u8; 64];
+        let mut to = from.clone();
+        to[63] = 8;
+        round_trip(&from, &to);
+        to[0] = 8;
+        to[31] = 8;
+        round_trip(&from, &to);
+    }
+}
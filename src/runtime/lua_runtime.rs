@@ -1,12 +1,15 @@
-use mlua::{Function, Lua, MultiValue, Table, Value};
+use mlua::{Function, Lua, MultiValue, Table, ToLua, Value};
 
+use crate::api::misc::{MenuItem, StatValue};
 use crate::runtime::Runtime;
-use crate::{api, draw_state, get_sprite_flag, set_sprite_flag};
+use crate::{api, draw_state, font, get_sprite_flag, set_sprite_flag, HEIGHT, WIDTH};
 pub struct LuaRuntime {
     lua: Lua,
 }
 
 impl LuaRuntime {
+    // The bindings spell out the Lua argument tuples of each API function
+    #[allow(clippy::type_complexity)]
     pub fn new(script: &[u8]) -> Self {
         let lua = Lua::new();
 
@@ -70,7 +73,7 @@ impl LuaRuntime {
                             x,
                             y,
                             r.unwrap_or(4),
-                            color.unwrap_or(i32::from(draw_state::get_pen_color(None))),
+                            color.unwrap_or(draw_state::get_pen_color(None)),
                         );
                         Ok(())
                     },
@@ -88,7 +91,7 @@ impl LuaRuntime {
                             x,
                             y,
                             r.unwrap_or(4),
-                            color.unwrap_or(i32::from(draw_state::get_pen_color(None))),
+                            color.unwrap_or(draw_state::get_pen_color(None)),
                         );
                         Ok(())
                     },
@@ -97,27 +100,168 @@ impl LuaRuntime {
             )
             .unwrap();
 
+        lua.globals()
+            .set(
+                "clip",
+                lua.create_function(
+                    |_,
+                     (x, y, w, h, clip_previous): (
+                        Option<f32>,
+                        Option<f32>,
+                        Option<f32>,
+                        Option<f32>,
+                        Option<bool>,
+                    )| {
+                        let clip_previous = match clip_previous.unwrap_or(false) {
+                            false => 0,
+                            true => 1,
+                        };
+
+                        Ok(match (x, y, w, h) {
+                            (Some(x), Some(y), Some(w), Some(h)) => api::gfx::clip(
+                                x as i32,
+                                y as i32,
+                                w as i32,
+                                h as i32,
+                                clip_previous,
+                            ),
+                            _ => api::gfx::clip(0, 0, WIDTH, HEIGHT, 0),
+                        })
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+
         lua.globals()
             .set(
                 "cls",
                 lua.create_function(|_, color: Option<i32>| {
-                    api::gfx::cls(color.unwrap_or(i32::from(draw_state::get_pen_color(None))));
+                    api::gfx::cls(color.unwrap_or(draw_state::get_pen_color(None)));
                     Ok(())
                 })
                 .unwrap(),
             )
             .unwrap();
 
+        lua.globals()
+            .set(
+                "color",
+                lua.create_function(|_, col: Option<i32>| Ok(api::gfx::color(col.unwrap_or(6))))
+                    .unwrap(),
+            )
+            .unwrap();
+
+        lua.globals()
+            .set(
+                "cursor",
+                lua.create_function(|_, (x, y, col): (Option<i32>, Option<i32>, Option<i32>)| {
+                    api::gfx::cursor(x.unwrap_or(0), y.unwrap_or(0), col);
+                    Ok(())
+                })
+                .unwrap(),
+            )
+            .unwrap();
+
+        lua.globals()
+            .set(
+                "fillp",
+                lua.create_function(|_, pattern: Option<f32>| {
+                    // PICO-8 stores the transparency flag in the fractional bit 0x0.8 of the
+                    // 16.16 fixed point pattern, its integer part is the floor
+                    let bits = (pattern.unwrap_or(0.0) * 65536.0).floor() as i64;
+                    let transparent = ((bits >> 15) & 1) as i32;
+                    api::gfx::fillp((bits >> 16) as i32, transparent);
+                    Ok(())
+                })
+                .unwrap(),
+            )
+            .unwrap();
+
+        lua.globals()
+            .set(
+                "line",
+                lua.create_function(
+                    |_,
+                     (a0, a1, a2, a3, a4): (
+                        Option<f32>,
+                        Option<f32>,
+                        Option<f32>,
+                        Option<f32>,
+                        Option<f32>,
+                    )| {
+                        let pen = draw_state::get_pen_color(None);
+                        match (a0, a1, a2, a3) {
+                            (Some(x0), Some(y0), Some(x1), Some(y1)) => api::gfx::line(
+                                x0 as i32,
+                                y0 as i32,
+                                x1 as i32,
+                                y1 as i32,
+                                a4.map_or(pen, |c| c as i32),
+                            ),
+                            (Some(x1), Some(y1), col, None) => api::gfx::line_to(
+                                x1 as i32,
+                                y1 as i32,
+                                col.map_or(pen, |c| c as i32),
+                            ),
+                            _ => api::gfx::line_reset(),
+                        }
+                        Ok(())
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+
+        lua.globals()
+            .set(
+                "oval",
+                lua.create_function(
+                    |_, (x0, y0, x1, y1, color): (f32, f32, f32, f32, Option<f32>)| {
+                        api::gfx::oval(
+                            x0 as i32,
+                            y0 as i32,
+                            x1 as i32,
+                            y1 as i32,
+                            color.map_or(draw_state::get_pen_color(None), |c| c as i32),
+                        );
+                        Ok(())
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+
+        lua.globals()
+            .set(
+                "ovalfill",
+                lua.create_function(
+                    |_, (x0, y0, x1, y1, color): (f32, f32, f32, f32, Option<f32>)| {
+                        api::gfx::ovalfill(
+                            x0 as i32,
+                            y0 as i32,
+                            x1 as i32,
+                            y1 as i32,
+                            color.map_or(draw_state::get_pen_color(None), |c| c as i32),
+                        );
+                        Ok(())
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+
         lua.globals()
             .set(
                 "rect",
-                lua.create_function(|_, args: (f32, f32, f32, f32, f32)| {
+                lua.create_function(|_, args: (f32, f32, f32, f32, Option<f32>)| {
                     api::gfx::rect(
                         args.0 as i32,
                         args.1 as i32,
                         args.2 as i32,
                         args.3 as i32,
-                        args.4 as i32,
+                        args.4
+                            .map_or(draw_state::get_pen_color(None), |c| c as i32),
                     );
                     Ok(())
                 })
@@ -128,13 +272,14 @@ impl LuaRuntime {
         lua.globals()
             .set(
                 "rectfill",
-                lua.create_function(|_, args: (f32, f32, f32, f32, f32)| {
+                lua.create_function(|_, args: (f32, f32, f32, f32, Option<f32>)| {
                     api::gfx::rectfill(
                         args.0 as i32,
                         args.1 as i32,
                         args.2 as i32,
                         args.3 as i32,
-                        args.4 as i32,
+                        args.4
+                            .map_or(draw_state::get_pen_color(None), |c| c as i32),
                     );
                     Ok(())
                 })
@@ -145,11 +290,36 @@ impl LuaRuntime {
         lua.globals()
             .set(
                 "pal",
-                lua.create_function(|_, (c0, c1, p): (Option<i32>, Option<i32>, Option<i32>)| {
-                    if c0.is_none() || c1.is_none() {
-                        api::gfx::clrpal();
-                    } else {
-                        api::gfx::pal(c0.unwrap(), c1.unwrap(), p.unwrap_or(0));
+                lua.create_function(|_, (c0, c1, p): (Value, Option<i32>, Option<i32>)| {
+                    match (c0, c1) {
+                        // pal({[c0] = c1, ...}, p) remaps every pair in the table
+                        (Value::Table(table), p) => {
+                            for pair in table.pairs::<i32, i32>() {
+                                let (c0, c1) = pair?;
+                                api::gfx::pal(c0, c1, p.unwrap_or(0));
+                            }
+                        }
+                        (Value::Integer(c0), Some(c1)) => {
+                            api::gfx::pal(c0 as i32, c1, p.unwrap_or(0));
+                        }
+                        (Value::Number(c0), Some(c1)) => {
+                            api::gfx::pal(c0 as i32, c1, p.unwrap_or(0));
+                        }
+                        _ => api::gfx::clrpal(),
+                    }
+                    Ok(())
+                })
+                .unwrap(),
+            )
+            .unwrap();
+
+        lua.globals()
+            .set(
+                "palrgb",
+                lua.create_function(|_, (c, rgb): (Option<i32>, Option<i32>)| {
+                    match (c, rgb) {
+                        (Some(c), Some(rgb)) => api::gfx::palrgb(c, rgb),
+                        _ => api::gfx::palrgb_reset(),
                     }
                     Ok(())
                 })
@@ -183,8 +353,13 @@ impl LuaRuntime {
         lua.globals()
             .set(
                 "pset",
-                lua.create_function(|_, args: (f32, f32, f32)| {
-                    api::gfx::pset(args.0 as i32, args.1 as i32, args.2 as i32);
+                lua.create_function(|_, args: (f32, f32, Option<f32>)| {
+                    api::gfx::pset(
+                        args.0 as i32,
+                        args.1 as i32,
+                        args.2
+                            .map_or(draw_state::get_pen_color(None), |c| c as i32),
+                    );
                     Ok(())
                 })
                 .unwrap(),
@@ -194,11 +369,31 @@ impl LuaRuntime {
         lua.globals()
             .set(
                 "print",
-                lua.create_function(|_, args: (String, i32, i32, i32)| {
-                    api::gfx::print(args.0, args.1, args.2, args.3);
-                    Ok(())
-                })
-                .unwrap(),
+                lua
+                    .create_function(
+                        |_,
+                         (string, x, y, col): (
+                            mlua::String,
+                            Option<f32>,
+                            Option<f32>,
+                            Option<f32>,
+                        )| {
+                            let string = font::p8scii_from_bytes(string.as_bytes());
+                            let pen = draw_state::get_pen_color(None);
+                            Ok(match (x, y) {
+                                (Some(x), Some(y)) => api::gfx::print(
+                                    &string,
+                                    x.floor() as i32,
+                                    y.floor() as i32,
+                                    col.map_or(pen, |col| col as i32),
+                                ),
+                                // print(str, col) prints at the cursor in col
+                                (Some(col), None) => api::gfx::print_cursor(&string, col as i32),
+                                _ => api::gfx::print_cursor(&string, pen),
+                            })
+                        },
+                    )
+                    .unwrap(),
             )
             .unwrap();
 
@@ -261,14 +456,14 @@ impl LuaRuntime {
                 lua.create_function(
                     |_,
                      (sx, sy, sw, sh, dx, dy, dw, dh, flip_x, flip_y): (
-                        i32,
-                        i32,
-                        i32,
-                        i32,
-                        i32,
-                        i32,
-                        Option<i32>,
-                        Option<i32>,
+                        f32,
+                        f32,
+                        f32,
+                        f32,
+                        f32,
+                        f32,
+                        Option<f32>,
+                        Option<f32>,
                         Option<bool>,
                         Option<bool>,
                     )| {
@@ -283,14 +478,14 @@ impl LuaRuntime {
                         };
 
                         api::gfx::sspr(
-                            sx,
-                            sy,
-                            sw,
-                            sh,
-                            dx,
-                            dy,
-                            dw.unwrap_or(1),
-                            dh.unwrap_or(1),
+                            sx as i32,
+                            sy as i32,
+                            sw as i32,
+                            sh as i32,
+                            dx as i32,
+                            dy as i32,
+                            dw.unwrap_or(sw) as i32,
+                            dh.unwrap_or(sh) as i32,
                             flip_x,
                             flip_y,
                         );
@@ -345,6 +540,40 @@ impl LuaRuntime {
             )
             .unwrap();
 
+        lua.globals()
+            .set(
+                "tline",
+                lua.create_function(
+                    |_,
+                     (x0, y0, x1, y1, mx, my, mdx, mdy, layers): (
+                        f32,
+                        f32,
+                        f32,
+                        f32,
+                        f32,
+                        f32,
+                        Option<f32>,
+                        Option<f32>,
+                        Option<i32>,
+                    )| {
+                        api::gfx::tline(
+                            x0 as i32,
+                            y0 as i32,
+                            x1 as i32,
+                            y1 as i32,
+                            mx,
+                            my,
+                            mdx.unwrap_or(0.125),
+                            mdy.unwrap_or(0.0),
+                            layers.unwrap_or(0),
+                        );
+                        Ok(())
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+
         lua.globals()
             .set(
                 "mset",
@@ -397,8 +626,13 @@ impl LuaRuntime {
         lua.globals()
             .set(
                 "btn",
-                lua.create_function(|_, (idx, player): (i32, Option<i32>)| {
-                    Ok(api::input::btn(idx, player.unwrap_or(0)) != 0)
+                lua.create_function(|_, (idx, player): (Option<i32>, Option<i32>)| {
+                    Ok(match idx {
+                        Some(idx) => {
+                            Value::Boolean(api::input::btn(idx, player.unwrap_or(0)) != 0)
+                        }
+                        None => Value::Integer(api::input::btn_bitfield() as i64),
+                    })
                 })
                 .unwrap(),
             )
@@ -407,8 +641,13 @@ impl LuaRuntime {
         lua.globals()
             .set(
                 "btnp",
-                lua.create_function(|_, (idx, player): (i32, Option<i32>)| {
-                    Ok(api::input::btnp(idx, player.unwrap_or(0)) != 0)
+                lua.create_function(|_, (idx, player): (Option<i32>, Option<i32>)| {
+                    Ok(match idx {
+                        Some(idx) => {
+                            Value::Boolean(api::input::btnp(idx, player.unwrap_or(0)) != 0)
+                        }
+                        None => Value::Integer(api::input::btnp_bitfield() as i64),
+                    })
                 })
                 .unwrap(),
             )
@@ -562,8 +801,11 @@ impl LuaRuntime {
         lua.globals()
             .set(
                 "srand",
-                lua.create_function(|_, x: i32| Ok(api::math::srand(x)))
-                    .unwrap(),
+                lua.create_function(|_, x: i32| {
+                    api::math::srand(x);
+                    Ok(())
+                })
+                .unwrap(),
             )
             .unwrap();
 
@@ -608,6 +850,85 @@ impl LuaRuntime {
                 .unwrap(),
             )
             .unwrap();
+
+        lua.globals()
+            .set(
+                "menuitem",
+                lua.create_function(
+                    |lua, (index, label, callback): (i32, Option<String>, Option<Function>)| {
+                        // The callback id is the index, the function is kept in the registry
+                        let key = format!("menuitem_{}", index);
+                        match callback {
+                            Some(callback) => lua.set_named_registry_value(&key, callback)?,
+                            None => lua.set_named_registry_value(&key, Value::Nil)?,
+                        }
+                        api::misc::menuitem(index, label, index);
+                        Ok(())
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+
+        lua.globals()
+            .set(
+                "cartdata",
+                lua.create_function(|_, id: String| Ok(api::misc::cartdata(id)))
+                    .unwrap(),
+            )
+            .unwrap();
+
+        lua.globals()
+            .set(
+                "dget",
+                lua.create_function(|_, idx: i32| Ok(api::misc::dget(idx)))
+                    .unwrap(),
+            )
+            .unwrap();
+
+        lua.globals()
+            .set(
+                "dset",
+                lua.create_function(|_, (idx, val): (i32, f32)| {
+                    api::misc::dset(idx, val);
+                    Ok(())
+                })
+                .unwrap(),
+            )
+            .unwrap();
+
+        lua.globals()
+            .set(
+                "peek",
+                lua.create_function(|_, addr: i32| Ok(api::misc::peek(addr)))
+                    .unwrap(),
+            )
+            .unwrap();
+
+        lua.globals()
+            .set(
+                "poke",
+                lua.create_function(|_, (addr, val): (i32, Option<i32>)| {
+                    api::misc::poke(addr, val.unwrap_or(0));
+                    Ok(())
+                })
+                .unwrap(),
+            )
+            .unwrap();
+
+        lua.globals()
+            .set(
+                "stat",
+                lua.create_function(|lua, n: i32| {
+                    Ok(match api::misc::stat(n) {
+                        StatValue::Number(val) => val.to_lua(lua)?,
+                        StatValue::Bool(val) => Value::Boolean(val),
+                        StatValue::String(val) => Value::String(lua.create_string(&val)?),
+                    })
+                })
+                .unwrap(),
+            )
+            .unwrap();
         
         lua.globals()
             .set(
@@ -749,7 +1070,9 @@ impl LuaRuntime {
             .unwrap();
 
         let x = script.iter().map(|f| *f as char).collect::<String>();
-        std::fs::write("./last.lua", x);
+        if let Err(why) = std::fs::write("./last.lua", x) {
+            println!("Unable to write last.lua. Reason: {}", why);
+        }
 
         lua.load(script).exec().unwrap();
 
@@ -768,7 +1091,11 @@ impl Runtime for LuaRuntime {
     }
 
     fn update(&mut self) {
-        match self.lua.globals().get::<&str, Function>("_update") {
+        let globals = self.lua.globals();
+        match globals
+            .get::<&str, Function>("_update60")
+            .or_else(|_| globals.get::<&str, Function>("_update"))
+        {
             Ok(func) => func,
             Err(_) => return,
         }
@@ -776,6 +1103,13 @@ impl Runtime for LuaRuntime {
         .unwrap();
     }
 
+    fn frame_rate(&self) -> f32 {
+        match self.lua.globals().get::<&str, Function>("_update60") {
+            Ok(_) => 60.0,
+            Err(_) => 30.0,
+        }
+    }
+
     fn draw(&mut self) {
         match self.lua.globals().get::<&str, Function>("_draw") {
             Ok(func) => func,
@@ -784,20 +1118,27 @@ impl Runtime for LuaRuntime {
         .call::<_, ()>(())
         .unwrap();
     }
-}
 
-pub fn sorted_squares(nums: Vec<i32>) -> Vec<i32> {
-    let mut negative: Vec<i32> = Vec::new();
-    let mut positive: Vec<i32> = Vec::new();
-    nums.iter().for_each(|x| {
-        if *x < 0 {
-            negative.push(x.pow(2));
-        } else {
-            positive.push(x.pow(2));
+    fn menuitem(&mut self, item: &MenuItem, buttons: i32) -> bool {
+        let key = format!("menuitem_{}", item.callback);
+        match self.lua.named_registry_value::<_, Function>(&key) {
+            Ok(func) => matches!(
+                func.call::<_, Value>(buttons).unwrap(),
+                Value::Boolean(true)
+            ),
+            Err(_) => false,
         }
-    });
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.lua.used_memory()
+    }
 
-    negative.reverse();
-    negative.append(&mut positive);
-    negative
+    fn save_state(&mut self) -> Result<Vec<u8>, String> {
+        Err("save states are only supported by WASM carts".to_string())
+    }
+
+    fn load_state(&mut self, _state: &[u8]) -> Result<(), String> {
+        Err("save states are only supported by WASM carts".to_string())
+    }
 }
@@ -1,14 +1,22 @@
 pub mod lua_runtime;
 pub mod wasm_runtime;
 
-pub enum Runtimes {
-    None,
-    Wasm,
-    Lua,
-}
+use crate::api::misc::MenuItem;
 
 pub trait Runtime {
     fn init(&mut self);
+    /// Calls `_update60` if the cart has it, `_update` otherwise
     fn update(&mut self);
     fn draw(&mut self);
+    /// 60 for carts with `_update60`, 30 otherwise
+    fn frame_rate(&self) -> f32;
+    /// Calls the callback of a pause menu entry with the buttons pressed, returns true if the
+    /// menu should stay open
+    fn menuitem(&mut self, item: &MenuItem, buttons: i32) -> bool;
+    /// Bytes of memory used by the cart
+    fn memory_usage(&self) -> usize;
+    /// Captures the state of the cart and the console memory, or why it can't be captured
+    fn save_state(&mut self) -> Result<Vec<u8>, String>;
+    /// Restores a state returned by `save_state`
+    fn load_state(&mut self, state: &[u8]) -> Result<(), String>;
 }
@@ -1,7 +1,15 @@
-use crate::{api, utils::read_cstr};
+use crate::api::misc::MenuItem;
+use crate::utils::{read_cbytes, read_cstr};
+use crate::{api, font, MEM};
 
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use core::panic;
-use std::{u32, usize};
+use std::collections::HashSet;
+use std::io::{Cursor, Read};
+use wasmparser::{ExternalKind, ImportSectionEntryType, Parser, Payload};
+
+const STATE_MAGIC: &[u8; 4] = b"W8ST";
+const WASM_PAGE_SIZE: usize = 0x10000;
 
 macro_rules! func_wrap {
     ($wasm_runtime:expr, $func:expr) => {
@@ -14,18 +22,16 @@ use wasmtime::*;
 
 pub struct WasmCallerWrapper<'a>(Caller<'a>);
 
-impl<'a> WasmCallerWrapper<'_> {
-    pub fn new(caller: Caller<'_>) -> WasmCallerWrapper {
-        let wrapper = WasmCallerWrapper(caller);
-        wrapper
+impl<'a> WasmCallerWrapper<'a> {
+    pub fn new(caller: Caller<'a>) -> WasmCallerWrapper<'a> {
+        WasmCallerWrapper(caller)
     }
 
     pub fn get_memory(&self) -> Memory {
-        let memory = match self.0.get_export("memory").unwrap() {
+        match self.0.get_export("memory").unwrap() {
             Extern::Memory(mem) => mem,
             _ => panic!("invalid memory type exported"),
-        };
-        memory
+        }
     }
 
     pub fn peek(&self, addr: i32) -> u8 {
@@ -39,6 +45,7 @@ impl<'a> WasmCallerWrapper<'_> {
         res
     }
 
+    #[allow(dead_code)]
     pub fn poke(&self, addr: i32, val: u8) {
         let memory = self.get_memory();
         if (addr as usize) < memory.data_size() {
@@ -56,18 +63,40 @@ pub struct WasmRuntime {
     instance: Option<Instance>,
     init: Option<Func>,
     update: Option<Func>,
+    /// True if `update` is `_update60`
+    update60: bool,
     draw: Option<Func>,
     memory: Option<Memory>,
+    /// Names of the exported mutable globals, saved in save states
+    mutable_globals: Vec<String>,
+    /// Why the cart's state can't be saved, if it can't
+    snapshot_error: Option<String>,
+    /// Hash of the module binary, states are refused by builds of other carts
+    module_hash: u64,
 }
 
 impl WasmRuntime {
-    fn print(caller: Caller, string_addr: i32, x: i32, y: i32, col: i32) {
-        api::gfx::print(
-            read_cstr(&WasmCallerWrapper::new(caller), string_addr),
-            x,
-            y,
-            col,
-        );
+    fn print(caller: Caller, string_addr: i32, x: i32, y: i32, col: i32) -> i32 {
+        let bytes = read_cbytes(&WasmCallerWrapper::new(caller), string_addr);
+        api::gfx::print(&font::p8scii_from_bytes(&bytes), x, y, col)
+    }
+
+    fn menuitem(caller: Caller, index: i32, label_addr: i32, callback: i32) {
+        let label = match label_addr {
+            0 => None,
+            _ => Some(read_cstr(&WasmCallerWrapper::new(caller), label_addr)),
+        };
+        api::misc::menuitem(index, label, callback);
+    }
+
+    fn cartdata(caller: Caller, id_addr: i32) -> i32 {
+        let id = read_cstr(&WasmCallerWrapper::new(caller), id_addr);
+        api::misc::cartdata(id) as i32
+    }
+
+    fn print_cursor(caller: Caller, string_addr: i32, col: i32) -> i32 {
+        let bytes = read_cbytes(&WasmCallerWrapper::new(caller), string_addr);
+        api::gfx::print_cursor(&font::p8scii_from_bytes(&bytes), col)
     }
 
     fn printh(caller: Caller, string_addr: i32) {
@@ -80,6 +109,80 @@ impl WasmRuntime {
         api::misc::load(str);
     }
 
+    /// Save states can only restore state that lives in the instance and is reachable through
+    /// its exports, so imported memories, tables and globals and unexported mutable globals are
+    /// refused
+    fn check_snapshottable(binary: &[u8]) -> Result<(), String> {
+        let mut global_count = 0;
+        let mut mutable_globals = Vec::new();
+        let mut exported_globals = HashSet::new();
+        for payload in Parser::new(0).parse_all(binary) {
+            match payload.map_err(|why| why.to_string())? {
+                Payload::ImportSection(reader) => {
+                    for import in reader {
+                        let import = import.map_err(|why| why.to_string())?;
+                        if let ImportSectionEntryType::Function(_) = import.ty {
+                            continue;
+                        }
+                        return Err(format!(
+                            "it imports something other than a function from `{}`",
+                            import.module
+                        ));
+                    }
+                }
+                Payload::GlobalSection(reader) => {
+                    for global in reader {
+                        if global.map_err(|why| why.to_string())?.ty.mutable {
+                            mutable_globals.push(global_count);
+                        }
+                        global_count += 1;
+                    }
+                }
+                Payload::ExportSection(reader) => {
+                    for export in reader {
+                        let export = export.map_err(|why| why.to_string())?;
+                        if let ExternalKind::Global = export.kind {
+                            exported_globals.insert(export.index);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let hidden = mutable_globals
+            .iter()
+            .filter(|idx| !exported_globals.contains(idx))
+            .count();
+        if hidden > 0 {
+            return Err(format!(
+                "{} mutable globals are not exported (for Rust carts link with \
+                 `-C link-arg=--export=__stack_pointer`)",
+                hidden
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// 64-bit FNV-1a, stable between builds unlike the std hashers
+    fn hash_binary(binary: &[u8]) -> u64 {
+        binary.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+
+    /// Tags the type of a global's value in save states
+    fn val_type_tag(val: &Val) -> Option<u8> {
+        match val {
+            Val::I32(_) => Some(0),
+            Val::I64(_) => Some(1),
+            Val::F32(_) => Some(2),
+            Val::F64(_) => Some(3),
+            _ => None,
+        }
+    }
+
     pub fn new(binary: &[u8]) -> WasmRuntime {
         let mut rt = WasmRuntime {
             engine: Engine::new(Config::new().interruptable(true)),
@@ -88,8 +191,12 @@ impl WasmRuntime {
             instance: None,
             init: None,
             update: None,
+            update60: false,
             draw: None,
             memory: None,
+            mutable_globals: Vec::new(),
+            snapshot_error: None,
+            module_hash: WasmRuntime::hash_binary(binary),
         };
 
         rt.store = Some(Store::new(&rt.engine));
@@ -98,18 +205,58 @@ impl WasmRuntime {
             panic!("Invalid Binary! Reason (from WASMTime): {}", why);
         }
         rt.module = Some(module.unwrap());
+        rt.snapshot_error = WasmRuntime::check_snapshottable(binary).err();
+        for export in rt.module.as_ref().unwrap().exports() {
+            if let ExternType::Global(global) = export.ty() {
+                if global.mutability() == Mutability::Var {
+                    rt.mutable_globals.push(export.name().to_string());
+                }
+            }
+        }
 
         let mut import_vec: Vec<Extern> = Vec::new();
         let mut missing_import_vec: Vec<String> = Vec::new();
         for import in rt.module.as_ref().unwrap().imports() {
             match import.name() {
+                "music" => {
+                    import_vec.push(func_wrap!(rt, |n: i32, fadems: i32, channelmask: i32| {
+                        api::music::music(n, fadems, channelmask as u32);
+                    }))
+                }
+                "sfx" => import_vec.push(func_wrap!(rt, api::music::sfx)),
+
+                "clip" => import_vec.push(func_wrap!(
+                    rt,
+                    |x: i32, y: i32, w: i32, h: i32, clip_previous: i32| {
+                        api::gfx::clip(x, y, w, h, clip_previous);
+                    }
+                )),
+                "circ" => import_vec.push(func_wrap!(rt, api::gfx::circ)),
+                "circfill" => import_vec.push(func_wrap!(rt, api::gfx::circfill)),
+                "oval" => import_vec.push(func_wrap!(rt, api::gfx::oval)),
+                "ovalfill" => import_vec.push(func_wrap!(rt, api::gfx::ovalfill)),
                 "cls" => import_vec.push(func_wrap!(rt, api::gfx::cls)),
+                "color" => import_vec.push(func_wrap!(rt, api::gfx::color)),
+                "cursor" => import_vec.push(func_wrap!(rt, |x: i32, y: i32, col: i32| {
+                    api::gfx::cursor(x, y, Some(col))
+                })),
+                "fillp" => import_vec.push(func_wrap!(rt, api::gfx::fillp)),
+                "line" => import_vec.push(func_wrap!(rt, api::gfx::line)),
+                "line_to" => import_vec.push(func_wrap!(rt, api::gfx::line_to)),
+                "line_reset" => import_vec.push(func_wrap!(rt, api::gfx::line_reset)),
                 "rect" => import_vec.push(func_wrap!(rt, api::gfx::rect)),
                 "rectfill" => import_vec.push(func_wrap!(rt, api::gfx::rectfill)),
+                "pal" => import_vec.push(func_wrap!(rt, api::gfx::pal)),
+                "palrgb" => import_vec.push(func_wrap!(rt, api::gfx::palrgb)),
+                "palrgb_reset" => import_vec.push(func_wrap!(rt, api::gfx::palrgb_reset)),
+                "clrpal" => import_vec.push(func_wrap!(rt, api::gfx::clrpal)),
                 "pget" => import_vec.push(func_wrap!(rt, api::gfx::pget)),
                 "pset" => import_vec.push(func_wrap!(rt, api::gfx::pset)),
                 "print" => import_vec.push(func_wrap!(rt, WasmRuntime::print)),
+                "print_cursor" => import_vec.push(func_wrap!(rt, WasmRuntime::print_cursor)),
                 "printh" => import_vec.push(func_wrap!(rt, WasmRuntime::printh)),
+                "sspr" => import_vec.push(func_wrap!(rt, api::gfx::sspr)),
+                "tline" => import_vec.push(func_wrap!(rt, api::gfx::tline)),
                 "spr" => import_vec.push(func_wrap!(
                     rt,
                     |idx: i32, x: i32, y: i32, w: f32, h: f32, flip_x: i32, flip_y: i32| {
@@ -119,6 +266,8 @@ impl WasmRuntime {
 
                 "btn" => import_vec.push(func_wrap!(rt, api::input::btn)),
                 "btnp" => import_vec.push(func_wrap!(rt, api::input::btnp)),
+                "btn_bitfield" => import_vec.push(func_wrap!(rt, api::input::btn_bitfield)),
+                "btnp_bitfield" => import_vec.push(func_wrap!(rt, api::input::btnp_bitfield)),
                 "key" => import_vec.push(func_wrap!(rt, api::input::key)),
 
                 "abs" => import_vec.push(func_wrap!(rt, api::math::abs)),
@@ -147,24 +296,30 @@ impl WasmRuntime {
                 "save" => import_vec.push(func_wrap!(rt, api::misc::exit)),
                 "load" => import_vec.push(func_wrap!(rt, WasmRuntime::load)),
                 "unload" => import_vec.push(func_wrap!(rt, api::misc::unload)),
+                "menuitem" => import_vec.push(func_wrap!(rt, WasmRuntime::menuitem)),
+                "cartdata" => import_vec.push(func_wrap!(rt, WasmRuntime::cartdata)),
+                "dget" => import_vec.push(func_wrap!(rt, api::misc::dget)),
+                "dset" => import_vec.push(func_wrap!(rt, api::misc::dset)),
+                "peek" => import_vec.push(func_wrap!(rt, api::misc::peek)),
+                "poke" => import_vec.push(func_wrap!(rt, api::misc::poke)),
+                "stat" => import_vec.push(func_wrap!(rt, |n: i32| api::misc::stat(n).as_f32())),
                 _ => missing_import_vec.push(import.name().to_owned()),
             }
             println!("Attempting to import {}", import.name());
         }
 
-        if missing_import_vec.len() != 0 {
-            panic!(format!(
+        if !missing_import_vec.is_empty() {
+            panic!(
                 "Missing {} imports: {:?}",
                 missing_import_vec.len(),
                 missing_import_vec
-            )
-            .as_str());
+            );
         }
 
         rt.instance = Some(
             Instance::new(
-                &rt.store.as_ref().unwrap(),
-                &rt.module.as_ref().unwrap(),
+                rt.store.as_ref().unwrap(),
+                rt.module.as_ref().unwrap(),
                 &import_vec[..],
             )
             .unwrap(),
@@ -189,12 +344,13 @@ impl WasmRuntime {
                 .get_func("_init")
                 .expect("`_init` was not an exported function"),
         );
+        let instance = rt.instance.as_ref().unwrap();
+        rt.update60 = instance.get_func("_update60").is_some();
         rt.update = Some(
-            rt.instance
-                .as_ref()
-                .unwrap()
-                .get_func("_update")
-                .expect("`_update` was not an exported function"),
+            instance
+                .get_func("_update60")
+                .or_else(|| instance.get_func("_update"))
+                .expect("`_update` or `_update60` was not an exported function"),
         );
         rt.draw = Some(
             rt.instance
@@ -220,4 +376,246 @@ impl Runtime for WasmRuntime {
     fn draw(&mut self) {
         self.draw.as_ref().unwrap().get0::<()>().unwrap()().unwrap();
     }
+
+    fn frame_rate(&self) -> f32 {
+        match self.update60 {
+            true => 60.0,
+            false => 30.0,
+        }
+    }
+
+    /// `item.callback` is an index into the cart's function table, which is what a function
+    /// pointer compiles to, the function takes the buttons and returns non-zero to stay open
+    fn menuitem(&mut self, item: &MenuItem, buttons: i32) -> bool {
+        let table = match self
+            .instance
+            .as_ref()
+            .unwrap()
+            .get_table("__indirect_function_table")
+        {
+            Some(table) => table,
+            None => return false,
+        };
+
+        match table.get(item.callback as u32) {
+            Some(Val::FuncRef(Some(func))) => match func.get1::<i32, i32>() {
+                Ok(callback) => callback(buttons).unwrap() != 0,
+                Err(why) => {
+                    println!("Invalid menu item callback. Reason: {}", why);
+                    false
+                }
+            },
+            _ => false,
+        }
+    }
+
+    /// The size of the cart's linear memory
+    fn memory_usage(&self) -> usize {
+        self.memory.as_ref().unwrap().data_size()
+    }
+
+    /// The module hash, the console memory, the exported mutable globals by name and the
+    /// linear memory
+    fn save_state(&mut self) -> Result<Vec<u8>, String> {
+        if let Some(why) = &self.snapshot_error {
+            return Err(format!("the cart can't be snapshotted, {}", why));
+        }
+
+        let mut state = STATE_MAGIC.to_vec();
+        state.write_u64::<LittleEndian>(self.module_hash).unwrap();
+        state.extend_from_slice(&MEM.lock().unwrap()[..]);
+
+        let instance = self.instance.as_ref().unwrap();
+        state
+            .write_u32::<LittleEndian>(self.mutable_globals.len() as u32)
+            .unwrap();
+        for name in &self.mutable_globals {
+            let val = instance.get_global(name).unwrap().get();
+            let (tag, bits) = match (WasmRuntime::val_type_tag(&val), val) {
+                (Some(tag), Val::I32(val)) => (tag, val as u32 as u64),
+                (Some(tag), Val::I64(val)) => (tag, val as u64),
+                (Some(tag), Val::F32(bits)) => (tag, bits as u64),
+                (Some(tag), Val::F64(bits)) => (tag, bits),
+                _ => return Err(format!("global `{}` is a reference", name)),
+            };
+            state.write_u16::<LittleEndian>(name.len() as u16).unwrap();
+            state.extend_from_slice(name.as_bytes());
+            state.push(tag);
+            state.write_u64::<LittleEndian>(bits).unwrap();
+        }
+
+        let memory = self.memory.as_ref().unwrap();
+        state
+            .write_u32::<LittleEndian>(memory.data_size() as u32)
+            .unwrap();
+        unsafe {
+            state.extend_from_slice(memory.data_unchecked());
+        }
+
+        Ok(state)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), String> {
+        if let Some(why) = &self.snapshot_error {
+            return Err(format!("the cart can't be snapshotted, {}", why));
+        }
+
+        // Read everything before changing anything so a bad state leaves the cart alone
+        let invalid = |_| "the state is truncated".to_string();
+        let mut reader = Cursor::new(state);
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic).map_err(invalid)?;
+        if &magic != STATE_MAGIC {
+            return Err("the file is not a save state".to_string());
+        }
+        if reader.read_u64::<LittleEndian>().map_err(invalid)? != self.module_hash {
+            return Err("the state is from a different cart or build of it".to_string());
+        }
+
+        let mut mem = vec![0u8; 0x8000];
+        reader.read_exact(&mut mem).map_err(invalid)?;
+
+        let global_count = reader.read_u32::<LittleEndian>().map_err(invalid)? as usize;
+        let mut globals = Vec::new();
+        for _ in 0..global_count {
+            let mut name = vec![0u8; reader.read_u16::<LittleEndian>().map_err(invalid)? as usize];
+            reader.read_exact(&mut name).map_err(invalid)?;
+            let tag = reader.read_u8().map_err(invalid)?;
+            let bits = reader.read_u64::<LittleEndian>().map_err(invalid)?;
+            globals.push((String::from_utf8_lossy(&name).to_string(), tag, bits));
+        }
+
+        let names: Vec<&String> = globals.iter().map(|(name, _, _)| name).collect();
+        if names != self.mutable_globals.iter().collect::<Vec<_>>() {
+            return Err("the state is from a different cart".to_string());
+        }
+
+        let instance = self.instance.as_ref().unwrap();
+        let mut values = Vec::new();
+        for (name, tag, bits) in globals {
+            let global = instance.get_global(&name).unwrap();
+            if WasmRuntime::val_type_tag(&global.get()) != Some(tag) {
+                return Err(format!(
+                    "global `{}` has a different type in the state",
+                    name
+                ));
+            }
+            let val = match tag {
+                0 => Val::I32(bits as u32 as i32),
+                1 => Val::I64(bits as i64),
+                2 => Val::F32(bits as u32),
+                _ => Val::F64(bits),
+            };
+            values.push((name, global, val));
+        }
+
+        let memory_len = reader.read_u32::<LittleEndian>().map_err(invalid)? as usize;
+        let mut linear = vec![0u8; memory_len];
+        reader.read_exact(&mut linear).map_err(invalid)?;
+
+        // Memory can't shrink, anything past the saved size is cleared instead
+        let memory = self.memory.as_ref().unwrap();
+        if memory_len > memory.data_size() {
+            let pages = (memory_len - memory.data_size()).div_ceil(WASM_PAGE_SIZE);
+            memory
+                .grow(pages as u32)
+                .map_err(|why| format!("unable to grow memory, {}", why))?;
+        }
+        unsafe {
+            let data = memory.data_unchecked_mut();
+            data[..memory_len].copy_from_slice(&linear);
+            data[memory_len..].fill(0);
+        }
+
+        for (name, global, val) in values {
+            global
+                .set(val)
+                .map_err(|why| format!("unable to set `{}`, {}", name, why))?;
+        }
+
+        MEM.lock().unwrap().copy_from_slice(&mem);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WasmRuntime;
+    use crate::runtime::Runtime;
+    use wasmtime::Val;
+
+    /// Counts updates in an exported mutable global and mirrors the count to linear memory
+    const CART: &str = r#"(module
+        (import "env" "cls" (func (param i32)))
+        (memory (export "memory") 1)
+        (global $count (export "count") (mut i32) (i32.const 0))
+        (func (export "_init"))
+        (func (export "_update")
+            (global.set $count (i32.add (global.get $count) (i32.const 1)))
+            (i32.store8 (i32.const 16) (global.get $count)))
+        (func (export "_draw")))"#;
+
+    fn count(rt: &WasmRuntime) -> (i32, u8) {
+        let global = match rt
+            .instance
+            .as_ref()
+            .unwrap()
+            .get_global("count")
+            .unwrap()
+            .get()
+        {
+            Val::I32(val) => val,
+            _ => unreachable!(),
+        };
+        let linear = unsafe { rt.memory.as_ref().unwrap().data_unchecked()[16] };
+        (global, linear)
+    }
+
+    fn load(cart: &str) -> WasmRuntime {
+        let mut rt = WasmRuntime::new(&wat::parse_str(cart).unwrap());
+        rt.init();
+        rt
+    }
+
+    fn run(updates: usize) -> WasmRuntime {
+        let mut rt = load(CART);
+        for _ in 0..updates {
+            rt.update();
+        }
+        rt
+    }
+
+    #[test]
+    fn state_round_trip() {
+        let mut rt = run(2);
+        let state = rt.save_state().unwrap();
+        rt.update();
+        assert_eq!(count(&rt), (3, 3));
+        rt.load_state(&state).unwrap();
+        assert_eq!(count(&rt), (2, 2));
+    }
+
+    #[test]
+    fn state_from_other_build_is_refused() {
+        let state = run(2).save_state().unwrap();
+        let other = CART.replace("(i32.const 1)", "(i32.const 2)");
+        let mut rt = load(&other);
+        rt.update();
+        let why = rt.load_state(&state).unwrap_err();
+        assert!(why.contains("different cart"), "{}", why);
+        assert_eq!(count(&rt), (2, 2));
+    }
+
+    #[test]
+    fn mistyped_global_leaves_cart_alone() {
+        let mut rt = run(2);
+        let mut state = rt.save_state().unwrap();
+        rt.update();
+        // Magic, module hash, console memory, global count, name length and name
+        let tag = 4 + 8 + 0x8000 + 4 + 2 + "count".len();
+        state[tag] = 1;
+        let why = rt.load_state(&state).unwrap_err();
+        assert!(why.contains("different type"), "{}", why);
+        assert_eq!(count(&rt), (3, 3));
+    }
 }
@@ -0,0 +1,93 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::runtime::Runtime;
+use crate::CART;
+
+/// Number of save state slots each cart has
+const SLOT_COUNT: usize = 10;
+
+/// Save state hotkeys, handled by the main loop after the input locks are dropped
+pub enum StateHotkey {
+    Save,
+    Load,
+    NextSlot,
+}
+
+/// Save states of the running cart, stored per cart and slot in the `states` directory of the
+/// config dir
+pub struct SaveStates {
+    slot: usize,
+}
+
+impl SaveStates {
+    pub fn new() -> Self {
+        SaveStates { slot: 0 }
+    }
+
+    pub fn handle(&mut self, hotkey: StateHotkey, runtime: &mut dyn Runtime) {
+        match hotkey {
+            StateHotkey::Save => self.save(runtime),
+            StateHotkey::Load => self.load(runtime),
+            StateHotkey::NextSlot => {
+                self.slot = (self.slot + 1) % SLOT_COUNT;
+                println!("Save state slot {}", self.slot);
+            }
+        }
+    }
+
+    fn path(&self) -> Option<PathBuf> {
+        let cart_path = CART.lock().unwrap().as_deref()?.path().clone();
+        let name = Path::new(&cart_path).file_name()?.to_str()?.to_string();
+        Some(
+            Path::new(&Config::get_config_dir_or_create()?)
+                .join("states")
+                .join(format!("{}.{}.state", name, self.slot)),
+        )
+    }
+
+    fn save(&self, runtime: &mut dyn Runtime) {
+        let path = match self.path() {
+            Some(path) => path,
+            None => return,
+        };
+
+        let state = match runtime.save_state() {
+            Ok(state) => state,
+            Err(why) => {
+                println!("Unable to save state. Reason: {}", why);
+                return;
+            }
+        };
+
+        if let Err(why) = fs::create_dir_all(path.parent().unwrap()) {
+            println!("Unable to create save state directory. Reason: {}", why);
+            return;
+        }
+        match fs::write(&path, state) {
+            Ok(_) => println!("Saved state to slot {}", self.slot),
+            Err(why) => println!("Unable to write save state. Reason: {}", why),
+        }
+    }
+
+    fn load(&self, runtime: &mut dyn Runtime) {
+        let path = match self.path() {
+            Some(path) => path,
+            None => return,
+        };
+
+        let state = match fs::read(&path) {
+            Ok(state) => state,
+            Err(_) => {
+                println!("No save state in slot {}", self.slot);
+                return;
+            }
+        };
+
+        match runtime.load_state(&state) {
+            Ok(_) => println!("Loaded state from slot {}", self.slot),
+            Err(why) => println!("Unable to load state. Reason: {}", why),
+        }
+    }
+}
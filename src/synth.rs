@@ -0,0 +1,524 @@
+use sdl2::audio::AudioCallback;
+
+use crate::{MEM, SYNTH};
+
+pub const SAMPLE_RATE: u32 = 22050;
+pub const CHANNEL_COUNT: usize = 4;
+
+// One PICO-8 sfx tick at 22050Hz, a note lasts `speed` ticks
+const SAMPLES_PER_TICK: f32 = 183.0;
+const MASTER_VOLUME: f32 = 0.25;
+
+const SFX_BASE: usize = 0x3200;
+const SFX_SIZE: usize = 68;
+const MUSIC_BASE: usize = 0x3100;
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Note {
+    pub pitch: u8,
+    pub waveform: u8,
+    pub volume: u8,
+    pub effect: u8,
+    pub custom: bool,
+}
+
+impl Note {
+    fn from_mem(mem: &[u8; 0x8000], sfx: i32, idx: i32) -> Note {
+        let addr = SFX_BASE + (sfx as usize * SFX_SIZE) + (idx as usize * 2);
+        let lo = mem[addr];
+        let hi = mem[addr + 1];
+        Note {
+            pitch: lo & 0b11_1111,
+            waveform: (lo >> 6) | ((hi & 0b1) << 2),
+            volume: (hi >> 1) & 0b111,
+            effect: (hi >> 4) & 0b111,
+            custom: (hi >> 7) != 0,
+        }
+    }
+}
+
+fn sfx_speed(mem: &[u8; 0x8000], sfx: i32) -> f32 {
+    mem[SFX_BASE + (sfx as usize * SFX_SIZE) + 65].max(1) as f32
+}
+
+fn sfx_loop(mem: &[u8; 0x8000], sfx: i32) -> (u8, u8) {
+    let addr = SFX_BASE + (sfx as usize * SFX_SIZE);
+    (mem[addr + 66].min(32), mem[addr + 67].min(32))
+}
+
+fn key_to_freq(key: f32) -> f32 {
+    440.0 * 2f32.powf((key - 33.0) / 12.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn triangle(phase: f32) -> f32 {
+    1.0 - 4.0 * (phase - 0.5).abs()
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Channel {
+    sfx: i32,
+    offset: f32,
+    end: f32,
+    loop_start: f32,
+    looping: bool,
+    looped: bool,
+    is_music: bool,
+    note: i32,
+    phase: f32,
+    detuned_phase: f32,
+    noise_state: u32,
+    noise: f32,
+    freq: f32,
+    volume: f32,
+    prev_freq: f32,
+    prev_volume: f32,
+}
+
+impl Channel {
+    const IDLE: Channel = Channel {
+        sfx: -1,
+        offset: 0.0,
+        end: 0.0,
+        loop_start: 0.0,
+        looping: false,
+        looped: false,
+        is_music: false,
+        note: -1,
+        phase: 0.0,
+        detuned_phase: 0.0,
+        noise_state: 0x2545_f491,
+        noise: 0.0,
+        freq: 0.0,
+        volume: 0.0,
+        prev_freq: 0.0,
+        prev_volume: 0.0,
+    };
+
+    fn start(&mut self, mem: &[u8; 0x8000], sfx: i32, offset: i32, length: i32, is_music: bool) {
+        let (loop_start, loop_end) = sfx_loop(mem, sfx);
+        let offset = offset.clamp(0, 31);
+        let looping = length <= 0 && loop_end > loop_start;
+        let end = if length > 0 {
+            (offset + length).min(32)
+        } else if looping {
+            loop_end as i32
+        } else if loop_end == 0 && loop_start > 0 {
+            loop_start as i32
+        } else {
+            32
+        };
+
+        *self = Channel::IDLE;
+        self.sfx = sfx;
+        self.offset = offset as f32;
+        self.end = end as f32;
+        self.loop_start = loop_start as f32;
+        self.looping = looping;
+        self.is_music = is_music;
+    }
+
+    fn stop(&mut self) {
+        *self = Channel::IDLE;
+    }
+
+    fn is_playing(&self) -> bool {
+        self.sfx >= 0
+    }
+
+    fn sample(&mut self, mem: &[u8; 0x8000]) -> f32 {
+        if !self.is_playing() {
+            return 0.0;
+        }
+
+        let speed = sfx_speed(mem, self.sfx);
+        let idx = self.offset as i32;
+        let note = Note::from_mem(mem, self.sfx, idx);
+        if idx != self.note {
+            self.prev_freq = self.freq;
+            self.prev_volume = self.volume;
+            self.note = idx;
+        }
+
+        let t = self.offset.fract();
+        self.freq = key_to_freq(note.pitch as f32);
+        self.volume = note.volume as f32 / 7.0;
+
+        let mut freq = self.freq;
+        let mut volume = self.volume;
+        match note.effect {
+            // Slide
+            1 => {
+                freq = lerp(self.prev_freq, freq, t);
+                volume = lerp(self.prev_volume, volume, t);
+            }
+            // Vibrato
+            2 => {
+                let seconds = t * speed * SAMPLES_PER_TICK / SAMPLE_RATE as f32;
+                let depth = (triangle((seconds * 7.5).fract()) + 1.0) / 2.0;
+                freq = lerp(freq, key_to_freq(note.pitch as f32 + 0.5), depth);
+            }
+            // Drop
+            3 => freq *= 1.0 - t,
+            // Fade in
+            4 => volume *= t,
+            // Fade out
+            5 => volume *= 1.0 - t,
+            // Arpeggio fast / slow
+            6 | 7 => {
+                let rate = if note.effect == 6 { 4.0 } else { 8.0 };
+                let step = ((self.offset * speed / rate) as i32) & 0b11;
+                let arp = Note::from_mem(mem, self.sfx, (idx & !0b11) + step);
+                freq = key_to_freq(arp.pitch as f32);
+            }
+            _ => {}
+        }
+
+        // Custom instruments are approximated by the waveform of the instrument's first note
+        let waveform = if note.custom {
+            Note::from_mem(mem, note.waveform as i32, 0).waveform
+        } else {
+            note.waveform
+        };
+
+        let step = freq / SAMPLE_RATE as f32;
+        if ((self.phase * 4.0) as i32) != (((self.phase + step) * 4.0) as i32) {
+            self.noise_state ^= self.noise_state << 13;
+            self.noise_state ^= self.noise_state >> 17;
+            self.noise_state ^= self.noise_state << 5;
+            self.noise = (self.noise_state as f32 / u32::MAX as f32) * 2.0 - 1.0;
+        }
+
+        let out = match waveform {
+            // Triangle
+            0 => triangle(self.phase),
+            // Tilted saw
+            1 => {
+                if self.phase < 0.875 {
+                    2.0 * self.phase / 0.875 - 1.0
+                } else {
+                    2.0 * (1.0 - self.phase) / 0.125 - 1.0
+                }
+            }
+            // Saw
+            2 => 2.0 * self.phase - 1.0,
+            // Square
+            3 => {
+                if self.phase < 0.5 {
+                    0.5
+                } else {
+                    -0.5
+                }
+            }
+            // Pulse
+            4 => {
+                if self.phase < 0.3125 {
+                    0.5
+                } else {
+                    -0.5
+                }
+            }
+            // Organ
+            5 => (triangle(self.phase) + triangle((self.phase * 2.0).fract()) * 0.5) / 1.5,
+            // Noise
+            6 => self.noise,
+            // Phaser
+            _ => (triangle(self.phase) + triangle(self.detuned_phase)) / 2.0,
+        } * volume;
+
+        self.phase = (self.phase + step).fract();
+        self.detuned_phase = (self.detuned_phase + step * (109.0 / 110.0)).fract();
+
+        self.offset += 1.0 / (speed * SAMPLES_PER_TICK);
+        if self.offset >= self.end {
+            if self.looping {
+                self.offset -= self.end - self.loop_start;
+                self.looped = true;
+            } else {
+                self.stop();
+            }
+        }
+
+        out
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Music {
+    pattern: i32,
+    channel_mask: u8,
+    samples_left: u32,
+    volume: f32,
+    fade_step: f32,
+    looped: bool,
+    patterns_played: i32,
+    pattern_samples: u32,
+}
+
+pub struct Synth {
+    channels: [Channel; CHANNEL_COUNT],
+    music: Option<Music>,
+}
+
+impl Synth {
+    pub fn new() -> Synth {
+        Synth {
+            channels: [Channel::IDLE; CHANNEL_COUNT],
+            music: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Synth::new();
+    }
+
+    pub fn sfx(&mut self, mem: &[u8; 0x8000], n: i32, channel: i32, offset: i32, length: i32) {
+        match n {
+            -1 => {
+                if channel < 0 {
+                    self.channels.iter_mut().for_each(|ch| ch.stop());
+                } else if (channel as usize) < CHANNEL_COUNT {
+                    self.channels[channel as usize].stop();
+                }
+            }
+            -2 => {
+                for (idx, ch) in self.channels.iter_mut().enumerate() {
+                    if channel < 0 || idx == channel as usize {
+                        ch.looping = false;
+                        ch.end = 32.0;
+                    }
+                }
+            }
+            0..=63 => {
+                if channel == -2 {
+                    self.channels
+                        .iter_mut()
+                        .filter(|ch| ch.sfx == n)
+                        .for_each(|ch| ch.stop());
+                    return;
+                }
+
+                let channel = if channel >= 0 && (channel as usize) < CHANNEL_COUNT {
+                    channel as usize
+                } else {
+                    self.free_channel()
+                };
+                self.channels[channel].start(mem, n, offset, length, false);
+            }
+            _ => {}
+        }
+    }
+
+    fn free_channel(&self) -> usize {
+        let reserved = match self.music {
+            Some(music) => music.channel_mask,
+            None => 0,
+        };
+
+        let is_free = |idx: usize| reserved & (1 << idx) == 0;
+        (0..CHANNEL_COUNT)
+            .find(|idx| is_free(*idx) && !self.channels[*idx].is_playing())
+            .or_else(|| (0..CHANNEL_COUNT).find(|idx| !self.channels[*idx].is_playing()))
+            .or_else(|| (0..CHANNEL_COUNT).find(|idx| !self.channels[*idx].is_music))
+            .unwrap_or(0)
+    }
+
+    pub fn music(&mut self, mem: &[u8; 0x8000], n: i32, fade_ms: i32, channel_mask: u8) {
+        let fade_step = if fade_ms > 0 {
+            1.0 / (fade_ms as f32 * SAMPLE_RATE as f32 / 1000.0)
+        } else {
+            0.0
+        };
+
+        if n < 0 {
+            match self.music.as_mut() {
+                Some(music) if fade_step > 0.0 => music.fade_step = -fade_step,
+                _ => self.stop_music(),
+            }
+            return;
+        }
+
+        self.stop_music();
+        self.music = Some(Music {
+            pattern: n.min(63),
+            channel_mask,
+            samples_left: 0,
+            volume: if fade_step > 0.0 { 0.0 } else { 1.0 },
+            fade_step,
+            looped: false,
+            patterns_played: 0,
+            pattern_samples: 0,
+        });
+        self.start_pattern(mem, n.min(63));
+    }
+
+    fn stop_music(&mut self) {
+        self.music = None;
+        self.channels
+            .iter_mut()
+            .filter(|ch| ch.is_music)
+            .for_each(|ch| ch.stop());
+    }
+
+    fn start_pattern(&mut self, mem: &[u8; 0x8000], pattern: i32) {
+        let addr = MUSIC_BASE + (pattern as usize * 4);
+        let mut length: Option<f32> = None;
+        let mut fallback_length: Option<f32> = None;
+        let mut enabled = false;
+
+        for idx in 0..CHANNEL_COUNT {
+            let val = mem[addr + idx];
+            if val & 0b0100_0000 != 0 {
+                if self.channels[idx].is_music {
+                    self.channels[idx].stop();
+                }
+                continue;
+            }
+
+            enabled = true;
+            let sfx = (val & 0b11_1111) as i32;
+            let channel = &mut self.channels[idx];
+            channel.start(mem, sfx, 0, 0, true);
+
+            let channel_length = channel.end * sfx_speed(mem, sfx) * SAMPLES_PER_TICK;
+            if !channel.looping && length.is_none() {
+                length = Some(channel_length);
+            }
+            if fallback_length.is_none() {
+                fallback_length = Some(channel_length);
+            }
+        }
+
+        if !enabled {
+            self.stop_music();
+            return;
+        }
+
+        if let Some(music) = self.music.as_mut() {
+            music.pattern = pattern;
+            music.pattern_samples = 0;
+            music.samples_left = length.or(fallback_length).unwrap_or(0.0) as u32;
+        }
+    }
+
+    fn next_pattern(mem: &[u8; 0x8000], pattern: i32) -> Option<(i32, bool)> {
+        let flag =
+            |pattern: i32, idx: usize| mem[MUSIC_BASE + (pattern as usize * 4) + idx] & 0x80 != 0;
+
+        if flag(pattern, 2) {
+            None
+        } else if flag(pattern, 1) {
+            let mut start = pattern;
+            while start > 0 && !flag(start, 0) {
+                start -= 1;
+            }
+            Some((start, true))
+        } else if pattern < 63 {
+            Some((pattern + 1, false))
+        } else {
+            None
+        }
+    }
+
+    fn tick_music(&mut self, mem: &[u8; 0x8000]) {
+        let music = match self.music.as_mut() {
+            Some(music) => music,
+            None => return,
+        };
+
+        music.volume += music.fade_step;
+        if music.fade_step < 0.0 && music.volume <= 0.0 {
+            self.stop_music();
+            return;
+        } else if music.volume >= 1.0 {
+            music.volume = 1.0;
+            music.fade_step = 0.0;
+        }
+
+        if music.samples_left > 0 {
+            music.samples_left -= 1;
+            music.pattern_samples += 1;
+            return;
+        }
+
+        match Synth::next_pattern(mem, music.pattern) {
+            Some((pattern, looped)) => {
+                music.looped |= looped;
+                music.patterns_played += 1;
+                self.start_pattern(mem, pattern);
+            }
+            None => self.stop_music(),
+        }
+    }
+
+    pub fn render(&mut self, mem: &[u8; 0x8000], out: &mut [f32]) {
+        for sample in out.iter_mut() {
+            self.tick_music(mem);
+            let music_volume = match self.music {
+                Some(music) => music.volume,
+                None => 1.0,
+            };
+
+            let mut mix = 0.0;
+            for channel in self.channels.iter_mut() {
+                let val = channel.sample(mem);
+                mix += if channel.is_music {
+                    val * music_volume
+                } else {
+                    val
+                };
+            }
+
+            *sample = (mix * MASTER_VOLUME).clamp(-1.0, 1.0);
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.music.is_some() || self.channels.iter().any(|ch| ch.is_playing())
+    }
+
+    /// The sfx playing on `channel`, or -1 if it is idle
+    pub fn channel_sfx(&self, channel: usize) -> i32 {
+        self.channels.get(channel).map_or(-1, |ch| ch.sfx)
+    }
+
+    /// The note (0-31) playing on `channel`, or -1 if it is idle
+    pub fn channel_note(&self, channel: usize) -> i32 {
+        match self.channels.get(channel) {
+            Some(ch) if ch.is_playing() => ch.offset as i32,
+            _ => -1,
+        }
+    }
+
+    /// The current music pattern, the number of patterns played since `music` was called and
+    /// the ticks played on the current pattern, or `None` if no music is playing
+    pub fn music_status(&self) -> Option<(i32, i32, i32)> {
+        self.music.map(|music| {
+            let ticks = music.pattern_samples as f32 / SAMPLES_PER_TICK;
+            (music.pattern, music.patterns_played, ticks as i32)
+        })
+    }
+
+    /// True once any looping sfx or the music has jumped back to its loop start
+    pub fn has_looped(&self) -> bool {
+        match self.music {
+            Some(music) => music.looped,
+            None => self.channels.iter().any(|ch| ch.looped),
+        }
+    }
+}
+
+pub struct SynthPlayback;
+
+impl AudioCallback for SynthPlayback {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        let mut synth = SYNTH.lock().unwrap();
+        let mem = MEM.lock().unwrap();
+        synth.render(&mem, out);
+    }
+}
@@ -1,16 +1,22 @@
 use crate::wasm_runtime::WasmCallerWrapper;
 use std::ffi::CString;
-pub struct FpsCounter(u32, u32);
+/// Counts frames per second, the third field is the count for the last full second
+pub struct FpsCounter(u32, u32, u32);
 
 impl FpsCounter {
     pub fn new(timer: u32) -> FpsCounter {
-        FpsCounter((timer / 1000) % 10, 0)
+        FpsCounter((timer / 1000) % 10, 0, 0)
+    }
+
+    pub fn fps(&self) -> u32 {
+        self.2
     }
 
     pub fn tick(&mut self, timer: u32) {
         let sec = (timer / 1000) % 10;
         if sec != self.0 {
             println!("FPS: {}", self.1);
+            self.2 = self.1;
             self.1 = 0;
             self.0 = sec % 10;
         }
@@ -18,7 +24,7 @@ impl FpsCounter {
     }
 }
 
-pub fn read_cstr(caller_wrapper: &WasmCallerWrapper, addr: i32) -> String {
+pub fn read_cbytes(caller_wrapper: &WasmCallerWrapper, addr: i32) -> Vec<u8> {
     let mut bytes: Vec<u8> = Vec::new();
     let mut offset = 0;
     loop {
@@ -30,5 +36,35 @@ pub fn read_cstr(caller_wrapper: &WasmCallerWrapper, addr: i32) -> String {
         offset += 1;
     }
 
-    CString::new(bytes).unwrap().into_string().unwrap()
+    bytes
+}
+
+pub fn read_cstr(caller_wrapper: &WasmCallerWrapper, addr: i32) -> String {
+    CString::new(read_cbytes(caller_wrapper, addr))
+        .unwrap()
+        .into_string()
+        .unwrap()
+}
+
+/// Compares `actual` against the checked in fixture at `tests/fixtures/{name}`, set
+/// `WARS8_BLESS=1` to write the fixture instead
+#[cfg(test)]
+pub fn assert_fixture(name: &str, actual: &[u8]) {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name);
+    if std::env::var_os("WARS8_BLESS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read(&path)
+        .unwrap_or_else(|why| panic!("Unable to read fixture {}. Reason: {}", name, why));
+    assert!(
+        expected == actual,
+        "Output differs from fixture {}, rerun with WARS8_BLESS=1 if the change is intended",
+        name
+    );
 }